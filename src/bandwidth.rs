@@ -0,0 +1,224 @@
+use std::time::Duration;
+
+use chrono::{Datelike, Local, NaiveTime, Timelike, Weekday};
+
+use crate::{
+    QBittorrentClient,
+    QBittorrentError
+};
+
+pub const EVERY_DAY: [Weekday; 7] = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun];
+pub const WEEKDAYS: [Weekday; 5] = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+pub const WEEKEND: [Weekday; 2] = [Weekday::Sat, Weekday::Sun];
+
+// Global limits in bytes/second, 0 means unlimited.
+// alternative switches the alternative speed limits mode first, None leaves it as it is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SpeedLimits {
+    pub download: usize,
+    pub upload: usize,
+    pub alternative: Option<bool>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    // exclusive; a window with end <= start runs past midnight into the next day
+    pub end: NaiveTime,
+    pub limits: SpeedLimits
+}
+
+impl TimeWindow {
+    pub fn new(days: &[Weekday], start: (u32, u32), end: (u32, u32), limits: SpeedLimits) -> Result<Self, QBittorrentError> {
+        let time = |(hour, minute): (u32, u32)| {
+            NaiveTime::from_hms_opt(hour, minute, 0)
+                .ok_or_else(|| QBittorrentError::BadInput(format!("invalid time: {:02}:{:02}", hour, minute)))
+        };
+
+        Ok(TimeWindow {
+            days: days.to_vec(),
+            start: time(start)?,
+            end: time(end)?,
+            limits
+        })
+    }
+
+    // days refer to the day the window starts on, e.g. a Friday 22:00-06:00 window also covers Saturday morning
+    pub fn contains<T: Datelike + Timelike>(&self, time: &T) -> bool {
+        let now = NaiveTime::from_hms_opt(time.hour(), time.minute(), time.second()).unwrap_or_default();
+        let weekday = time.weekday();
+
+        if self.start < self.end {
+            self.days.contains(&weekday) && self.start <= now && now < self.end
+        } else {
+            (self.days.contains(&weekday) && now >= self.start) || (self.days.contains(&weekday.pred()) && now < self.end)
+        }
+    }
+}
+
+// Applies a weekly timetable of global speed limits with set_download_limit/set_upload_limit.
+// Windows are checked in the order they were added, the first one containing the current local time wins.
+// qBittorrent's own scheduler (SpeedSchedule) should be disabled, otherwise both fight over the alternative mode.
+pub struct BandwidthScheduler {
+    windows: Vec<TimeWindow>,
+    default_limits: SpeedLimits,
+    interval: Duration,
+    // what the server is known to have; None forces reading the server state again
+    applied: Option<SpeedLimits>,
+    session: Option<u64>
+}
+
+impl BandwidthScheduler {
+    pub fn new(default_limits: SpeedLimits) -> Self {
+        BandwidthScheduler {
+            windows: Vec::new(),
+            default_limits,
+            interval: Duration::from_secs(60),
+            applied: None,
+            session: None
+        }
+    }
+
+    pub fn window(mut self, window: TimeWindow) -> Self {
+        self.windows.push(window);
+        self
+    }
+
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn limits_at<T: Datelike + Timelike>(&self, time: &T) -> SpeedLimits {
+        self.windows.iter()
+            .find(|window| window.contains(time))
+            .map_or(self.default_limits, |window| window.limits)
+    }
+
+    // Applies the limits for the current local time, returns them
+    pub async fn tick(&mut self, client: &QBittorrentClient) -> Result<SpeedLimits, QBittorrentError> {
+        let limits = self.limits_at(&Local::now());
+
+        // the client logged in again, qBittorrent was probably restarted and has its own limits again
+        let session = client.session_generation().await;
+        if self.session != Some(session) {
+            self.applied = None;
+            self.session = Some(session);
+        }
+
+        if self.applied != Some(limits) {
+            if let Err(err) = self.apply(client, limits).await {
+                self.applied = None;
+                return Err(err);
+            }
+
+            self.applied = Some(limits);
+        }
+
+        Ok(limits)
+    }
+
+    // Runs forever; errors (e.g. qBittorrent being unreachable) are passed to on_error and the state is reconciled on the next tick
+    pub async fn run<F: FnMut(QBittorrentError)>(mut self, client: &QBittorrentClient, mut on_error: F) {
+        loop {
+            if let Err(err) = self.tick(client).await {
+                on_error(err);
+            }
+
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+
+    async fn apply(&self, client: &QBittorrentClient, limits: SpeedLimits) -> Result<(), QBittorrentError> {
+        let known = self.applied;
+
+        // set_download_limit/set_upload_limit change the limits of the currently active mode, so switch it first
+        if let Some(alternative) = limits.alternative {
+            if known.and_then(|known| known.alternative) != Some(alternative) {
+                client.set_alternative_speed_limits(alternative).await?;
+            }
+        }
+
+        let mode_changed = limits.alternative.is_some() && known.map(|known| known.alternative) != Some(limits.alternative);
+
+        let download = match known {
+            Some(known) if !mode_changed => known.download,
+            _ => client.download_limit().await?
+        };
+
+        if download != limits.download {
+            client.set_download_limit(limits.download).await?;
+        }
+
+        let upload = match known {
+            Some(known) if !mode_changed => known.upload,
+            _ => client.upload_limit().await?
+        };
+
+        if upload != limits.upload {
+            client.set_upload_limit(limits.upload).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::*;
+
+    // 2024-06-03 is a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 6, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn limits(download: usize) -> SpeedLimits {
+        SpeedLimits { download, upload: 0, alternative: None }
+    }
+
+    #[test]
+    fn window_within_a_day() {
+        let window = TimeWindow::new(&WEEKDAYS, (8, 0), (18, 0), limits(1)).unwrap();
+
+        assert!(window.contains(&at(3, 8, 0)));
+        assert!(window.contains(&at(3, 17, 59)));
+        assert!(!window.contains(&at(3, 18, 0)));
+        assert!(!window.contains(&at(8, 12, 0)));
+    }
+
+    #[test]
+    fn window_wrapping_midnight() {
+        let window = TimeWindow::new(&[Weekday::Fri], (22, 0), (6, 0), limits(1)).unwrap();
+
+        // Friday evening and Saturday morning, but not Friday morning
+        assert!(window.contains(&at(7, 23, 0)));
+        assert!(window.contains(&at(8, 5, 59)));
+        assert!(!window.contains(&at(8, 6, 0)));
+        assert!(!window.contains(&at(7, 5, 0)));
+
+        // Sunday night runs into Monday (Monday.pred() is Sunday)
+        let window = TimeWindow::new(&[Weekday::Sun], (23, 0), (1, 0), limits(1)).unwrap();
+
+        assert!(window.contains(&at(9, 23, 30)));
+        assert!(window.contains(&at(10, 0, 30)));
+        assert!(!window.contains(&at(4, 0, 30)));
+    }
+
+    #[test]
+    fn first_window_wins() {
+        let scheduler = BandwidthScheduler::new(limits(0))
+            .window(TimeWindow::new(&EVERY_DAY, (8, 0), (12, 0), limits(1)).unwrap())
+            .window(TimeWindow::new(&EVERY_DAY, (0, 0), (0, 0), limits(2)).unwrap());
+
+        assert_eq!(scheduler.limits_at(&at(3, 9, 0)), limits(1));
+        assert_eq!(scheduler.limits_at(&at(3, 13, 0)), limits(2));
+
+        let scheduler = BandwidthScheduler::new(limits(0))
+            .window(TimeWindow::new(&WEEKEND, (8, 0), (12, 0), limits(1)).unwrap());
+
+        assert_eq!(scheduler.limits_at(&at(3, 9, 0)), limits(0));
+    }
+}
@@ -1,22 +1,85 @@
-use reqwest::{Client, Url};
-use tokio::sync::RwLock;
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    future::Future,
+    sync::Arc,
+    time::Duration
+};
+
+use futures_util::{stream, Stream};
+
+use reqwest::{
+    cookie::{CookieStore, Jar},
+    Client, Method, Request, RequestBuilder, Response, StatusCode, Url
+};
+use serde::de::DeserializeOwned;
+use tokio::sync::{Mutex, RwLock};
 
 use crate::{
     models::{
-        AppBuildInfo, AppPreferences, GlobalTransferInfo, JsonObject, Log, LogParams, PeerLog, SyncMainData, Torrent, TorrentGenericProperties, TorrentListParams, TorrentTracker, TorrentWebSeed
+        AppBuildInfo, AppPreferences, GlobalTransferInfo, JsonObject, Log, LogParams, PeerLog, SyncMainData, Torrent, TorrentGenericProperties, TorrentListParams, TorrentSortField, TorrentTracker, TorrentWebSeed
     },
-    QBittorrentError
+    version::{ApiVersion, AppVersion, Capabilities},
+    PreferenceChange,
+    QBittorrentError,
+    RetryPolicy,
+    SpeedSchedule
 };
 
+#[derive(Clone)]
+struct Credentials {
+    username: String,
+    password: String
+}
+
+// Authentication required by a reverse proxy in front of qBittorrent, sent with every request
+#[derive(Clone)]
+pub enum ProxyAuth {
+    Basic {
+        username: String,
+        password: Option<String>
+    },
+    // e.g. an API key header: ProxyAuth::Header { name: "X-Api-Key".to_string(), value: key }
+    Header {
+        name: String,
+        value: String
+    }
+}
+
+// secrets are redacted, clients and their configuration end up in logs
+impl fmt::Debug for ProxyAuth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProxyAuth::Basic { username, password } => f.debug_struct("Basic")
+                .field("username", username)
+                .field("password", &password.as_ref().map(|_| "<redacted>"))
+                .finish(),
+            ProxyAuth::Header { name, .. } => f.debug_struct("Header")
+                .field("name", name)
+                .field("value", &"<redacted>")
+                .finish()
+        }
+    }
+}
+
 pub struct QBittorrentClient {
     http_client: Client,
-    base_url: RwLock<Url>
+    cookie_jar: Arc<Jar>,
+    base_url: RwLock<Url>,
+    credentials: RwLock<Option<Credentials>>,
+    proxy_auth: RwLock<Option<ProxyAuth>>,
+    retry_policy: RwLock<RetryPolicy>,
+    api_version: RwLock<Option<ApiVersion>>,
+    alt_speed_lock: Mutex<()>,
+    // Incremented on every re-login, so requests that failed with an already replaced session don't log in again
+    session: Mutex<u64>
 }
 
 impl QBittorrentClient {
     pub async fn new<S: ToString>(url: S) -> Result<Self, QBittorrentError> {
+        let cookie_jar = Arc::new(Jar::default());
         let http_client = Client::builder()
-            .cookie_store(true)
+            .cookie_provider(cookie_jar.clone())
             .build()?;
 
         let base_url = Url::parse(&url.to_string())?;
@@ -24,100 +87,301 @@ impl QBittorrentClient {
 
         Ok(QBittorrentClient {
             http_client,
-            base_url
+            cookie_jar,
+            base_url,
+            credentials: RwLock::new(None),
+            proxy_auth: RwLock::new(None),
+            retry_policy: RwLock::new(RetryPolicy::disabled()),
+            api_version: RwLock::new(None),
+            alt_speed_lock: Mutex::new(()),
+            session: Mutex::new(0)
         })
     }
 
+    // Remembers the credentials, so the client logs in again and retries the request once the session has expired (403 response)
+    pub async fn set_credentials<S: ToString>(&self, username: S, password: S) {
+        let mut credentials = self.credentials.write().await;
+        *credentials = Some(Credentials {
+            username: username.to_string(),
+            password: password.to_string()
+        });
+    }
+
+    pub async fn clear_credentials(&self) {
+        let mut credentials = self.credentials.write().await;
+        *credentials = None;
+    }
+
+    pub async fn set_proxy_auth(&self, auth: Option<ProxyAuth>) {
+        let mut proxy_auth = self.proxy_auth.write().await;
+        *proxy_auth = auth;
+    }
+
+    async fn with_proxy_auth(&self, req: RequestBuilder) -> RequestBuilder {
+        match *self.proxy_auth.read().await {
+            Some(ProxyAuth::Basic { ref username, ref password }) => req.basic_auth(username, password.as_ref()),
+            Some(ProxyAuth::Header { ref name, ref value }) => req.header(name.as_str(), value.as_str()),
+            None => req
+        }
+    }
+
+    pub async fn set_retry_policy(&self, policy: RetryPolicy) {
+        let mut retry_policy = self.retry_policy.write().await;
+        *retry_policy = policy;
+    }
+
+    async fn send(&self, req: RequestBuilder) -> Result<ApiResponse, QBittorrentError> {
+        let req = self.with_proxy_auth(req).await.build()?;
+
+        #[cfg(feature = "tracing")]
+        return traced(req.method().clone(), req.url().path().to_string(), self.send_with_retries(req)).await;
+
+        #[cfg(not(feature = "tracing"))]
+        self.send_with_retries(req).await
+    }
+
+    async fn send_with_retries(&self, mut req: Request) -> Result<ApiResponse, QBittorrentError> {
+        let policy = self.retry_policy.read().await.clone();
+        let idempotent = req.method() == Method::GET || policy.retry_non_idempotent;
+
+        // per attempt, covering the response body too
+        if policy.timeout.is_some() {
+            *req.timeout_mut() = policy.timeout;
+        }
+
+        let mut attempt = 0;
+
+        loop {
+            let Some(attempt_req) = req.try_clone() else {
+                return self.execute(req).await;
+            };
+
+            match self.execute(attempt_req).await {
+                Err(err) if idempotent && policy.should_retry(attempt, &err) => {
+                    tokio::time::sleep(policy.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                res => return res
+            }
+        }
+    }
+
+    async fn execute(&self, req: Request) -> Result<ApiResponse, QBittorrentError> {
+        let retry_req = if self.credentials.read().await.is_some() {
+            req.try_clone()
+        } else {
+            None
+        };
+
+        let session = *self.session.lock().await;
+        let res = self.http_client.execute(req).await?;
+
+        let res = match retry_req {
+            Some(retry_req) if res.status() == StatusCode::FORBIDDEN => {
+                self.relogin(session).await?;
+                self.http_client.execute(retry_req).await?
+            }
+            _ => res
+        };
+
+        check_response(ApiResponse::read(res).await?)
+    }
+
+    #[cfg(feature = "chrono")]
+    pub(crate) async fn session_generation(&self) -> u64 {
+        *self.session.lock().await
+    }
+
+    async fn relogin(&self, failed_session: u64) -> Result<(), QBittorrentError> {
+        let mut session = self.session.lock().await;
+
+        // another request has already logged in again while we were waiting for the lock
+        if *session != failed_session {
+            return Ok(());
+        }
+
+        let credentials = self.credentials.read().await.clone();
+
+        if let Some(credentials) = credentials {
+            self.login(credentials.username, credentials.password).await?;
+            *session += 1;
+
+            // an expired session usually means qBittorrent was restarted, possibly upgraded
+            *self.api_version.write().await = None;
+        }
+
+        Ok(())
+    }
+
     async fn build_url(&self, endpoint: &str) -> Result<Url, QBittorrentError> {
         let base_url = self.base_url.read().await;
         Ok(base_url.join(endpoint)?)
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub async fn login<S: ToString>(&self, username: S, password: S) -> Result<(), QBittorrentError> {
         let base_url = self.base_url.read().await;
         let login_url = base_url.join("/api/v2/auth/login")?;
         
-        let res = self.http_client.post(login_url)
-            .header("Content-Type", "application/x-www-form-urlencoded")
+        let req = self.http_client.post(login_url)
             .header("Referer", base_url.to_string())
-            .body(format!("username={}&password={}", username.to_string(), password.to_string()))
-            .send().await?;
+            .form(&[
+                ("username", username.to_string()),
+                ("password", password.to_string())
+            ]);
+        let req = self.with_proxy_auth(req).await.build()?;
+
+        // sent directly, a login failing with 403 must not trigger another login
+        #[cfg(feature = "tracing")]
+        let res = traced(req.method().clone(), req.url().path().to_string(), async move { ApiResponse::read(self.http_client.execute(req).await?).await }).await?;
+
+        #[cfg(not(feature = "tracing"))]
+        let res = ApiResponse::read(self.http_client.execute(req).await?).await?;
+
+        // qBittorrent bans the IP address after too many failed attempts
+        if res.status == StatusCode::FORBIDDEN {
+            return Err(QBittorrentError::IpBanned);
+        }
 
-        if res.status().is_success() {
-            Ok(())
-        } else {
-            Err(QBittorrentError::AuthFailed)
+        if !res.status.is_success() {
+            return Err(QBittorrentError::AuthFailed);
+        }
+
+        // wrong credentials are reported with 200 OK and "Fails." body
+        match res.body.trim() {
+            "Ok." if self.has_session_cookie(&base_url) => Ok(()),
+            "Fails." => Err(QBittorrentError::InvalidCredentials),
+            _ => Err(QBittorrentError::AuthFailed)
         }
     }
 
+    // qBittorrent 5.x names the cookie QBT_SID_<port> instead of SID
+    fn has_session_cookie(&self, url: &Url) -> bool {
+        let Some(cookies) = self.cookie_jar.cookies(url) else {
+            return false;
+        };
+
+        cookies.to_str().unwrap_or_default()
+            .split(';')
+            .filter_map(|cookie| cookie.trim().split_once('='))
+            .any(|(name, _)| name == "SID" || name.starts_with("QBT_SID_"))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub async fn logout(&self) -> Result<(), QBittorrentError> {
         let logout_url = self.build_url("/api/v2/auth/logout").await?;
-        self.http_client.post(logout_url).send().await?;
+        self.send(self.http_client.post(logout_url)).await?;
 
         Ok(())
     }
 
-    pub async fn application_version(&self) -> Result<String, QBittorrentError> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub async fn application_version(&self) -> Result<AppVersion, QBittorrentError> {
         let app_ver_url = self.build_url("/api/v2/app/version").await?;
-        let res = self.http_client.get(app_ver_url).send().await?;
+        let res = self.send(self.http_client.get(app_ver_url)).await?;
 
-        Ok(res.text().await?)
+        res.body.parse::<AppVersion>()
     }
 
-    pub async fn api_version(&self) -> Result<String, QBittorrentError> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub async fn api_version(&self) -> Result<ApiVersion, QBittorrentError> {
         let api_ver_url = self.build_url("/api/v2/app/webapiVersion").await?;
-        let res = self.http_client.get(api_ver_url).send().await?;
+        let res = self.send(self.http_client.get(api_ver_url)).await?;
+
+        res.body.parse::<ApiVersion>()
+    }
+
+    // Fetched once and cached, used to pick the right endpoints for the connected server
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub async fn server_api_version(&self) -> Result<ApiVersion, QBittorrentError> {
+        if let Some(api_version) = *self.api_version.read().await {
+            return Ok(api_version);
+        }
+
+        let api_version = self.api_version().await?;
+        *self.api_version.write().await = Some(api_version);
+
+        Ok(api_version)
+    }
 
-        Ok(res.text().await?)
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub async fn capabilities(&self) -> Result<Capabilities, QBittorrentError> {
+        Ok(Capabilities::for_version(self.server_api_version().await?))
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub async fn build_info(&self) -> Result<AppBuildInfo, QBittorrentError> {
         let build_info_url = self.build_url("/api/v2/app/buildInfo").await?;
-        let res = self.http_client.get(build_info_url).send().await?;
+        let res = self.send(self.http_client.get(build_info_url)).await?;
+
+        // the build info is still useful if the version can't be fetched or parsed
+        let mut build_info = decode::<AppBuildInfo>(res)?;
+        build_info.version = self.application_version().await.ok();
 
-        Ok(res.json::<AppBuildInfo>().await?)
+        Ok(build_info)
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub async fn shutdown(&self) -> Result<(), QBittorrentError> {
         let shutdown_url = self.build_url("/api/v2/app/shutdown").await?;
-        self.http_client.post(shutdown_url).send().await?;
+        self.send(self.http_client.post(shutdown_url)).await?;
 
         Ok(())
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub async fn preferences(&self) -> Result<AppPreferences, QBittorrentError> {
         let preferences_url = self.build_url("/api/v2/app/preferences").await?;
-        let res = self.http_client.get(preferences_url).send().await?;
+        let res = self.send(self.http_client.get(preferences_url)).await?;
 
-        if res.status().as_u16() == 400 {
-            Err(QBittorrentError::BadRequest)
-        } else {
-            Ok(res.json::<AppPreferences>().await?)
-        }
+        decode::<AppPreferences>(res)
     }
 
-    pub async fn set_preferences(&self, obj: JsonObject) -> Result<(), QBittorrentError> {
+    // Accepts an AppPreferencesPatch, e.g. AppPreferences { max_active_downloads: Some(5), ..Default::default() }, or a raw JsonObject
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub async fn set_preferences<P: Into<JsonObject>>(&self, prefs: P) -> Result<(), QBittorrentError> {
+        let obj = prefs.into();
         let set_pref_url = self.build_url("/api/v2/app/setPreferences").await?;
 
-        let res = self.http_client.post(set_pref_url)
-            .header("Content-Type", "application/x-www-form-urlencoded")
-            .body(format!("json={}", obj.0.to_string()))
-            .send().await?;
+        let req = self.http_client.post(set_pref_url)
+            .form(&[("json", obj.0.to_string())]);
+        self.send(req).await?;
 
-        if res.status().as_u16() == 400 {
-            Err(QBittorrentError::BadRequest)
-        } else {
-            Ok(())
+        Ok(())
+    }
+
+    // Sends only the preferences that differ from the server; with dry_run nothing is sent
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(dry_run)))]
+    pub async fn apply_preferences(&self, desired: &AppPreferences, dry_run: bool) -> Result<Vec<PreferenceChange>, QBittorrentError> {
+        let live = self.preferences().await?;
+        let changes = desired.diff(&live);
+
+        if !dry_run && !changes.is_empty() {
+            self.set_preferences(changes.iter().cloned().collect::<JsonObject>()).await?;
         }
+
+        Ok(changes)
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub async fn speed_schedule(&self) -> Result<SpeedSchedule, QBittorrentError> {
+        SpeedSchedule::from_preferences(&self.preferences().await?)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub async fn set_speed_schedule(&self, schedule: &SpeedSchedule) -> Result<(), QBittorrentError> {
+        self.set_preferences(schedule.to_preferences()?).await
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub async fn default_save_path(&self) -> Result<String, QBittorrentError> {
         let def_save_path_url = self.build_url("/api/v2/app/defaultSavePath").await?;
-        let res = self.http_client.get(def_save_path_url).send().await?;
+        let res = self.send(self.http_client.get(def_save_path_url)).await?;
 
-        Ok(res.text().await?)
+        Ok(res.body)
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(last_known_id = params.last_known_id)))]
     pub async fn logs(&self, params: LogParams) -> Result<Vec<Log>, QBittorrentError> {
         let mut log_url = self.build_url("/api/v2/log/main").await?;
         
@@ -132,16 +396,17 @@ impl QBittorrentClient {
             .append_pair("critical", bool_to_str(params.critical))
             .append_pair("last_known_id", &params.last_known_id.to_string());
 
-        let res = self.http_client.get(log_url).send().await?;
-        Ok(res.json::<Vec<Log>>().await?)
+        let res = self.send(self.http_client.get(log_url)).await?;
+        decode::<Vec<Log>>(res)
     }
 
     // Exclude messages with "message id" <= last_known_id (default: -1)
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(last_known_id = ?last_known_id)))]
     pub async fn peer_logs(&self, last_known_id: Option<usize>) -> Result<Vec<PeerLog>, QBittorrentError> {
         let mut peers_url = self.build_url("/api/v2/log/peers").await?;
 
-        let lki = if last_known_id.is_some() {
-            last_known_id.unwrap().to_string()
+        let lki = if let Some(last_known_id) = last_known_id {
+            last_known_id.to_string()
         } else {
             "-1".to_string()
         };
@@ -149,114 +414,157 @@ impl QBittorrentClient {
         peers_url.query_pairs_mut()
             .append_pair("last_known_id", &lki);
 
-        let res = self.http_client.get(peers_url).send().await?;
+        let res = self.send(self.http_client.get(peers_url)).await?;
 
-        Ok(res.json::<Vec<PeerLog>>().await?)
+        decode::<Vec<PeerLog>>(res)
     }
 
+    // Polls logs every interval and yields only new entries, forever; params.last_known_id is the starting point
+    pub fn tail_logs(&self, params: LogParams, interval: Duration) -> impl Stream<Item = Result<Log, QBittorrentError>> + '_ {
+        let last_known_id = usize::try_from(params.last_known_id).ok();
+
+        tail(last_known_id, interval, |log: &Log| log.id, move |last_known_id| {
+            self.logs(LogParams {
+                last_known_id: last_known_id.map_or(-1, |id| id as isize),
+                ..params.clone()
+            })
+        })
+    }
+
+    pub fn tail_peer_logs(&self, last_known_id: Option<usize>, interval: Duration) -> impl Stream<Item = Result<PeerLog, QBittorrentError>> + '_ {
+        tail(last_known_id, interval, |log: &PeerLog| log.id, move |last_known_id| self.peer_logs(last_known_id))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(rid = ?response_id)))]
     pub async fn sync_main_data(&self, response_id: Option<usize>) -> Result<SyncMainData, QBittorrentError> {
         let mut sync_url = self.build_url("/api/v2/sync/maindata").await?;
         sync_url.query_pairs_mut()
             .append_pair("rid", &response_id.unwrap_or(0).to_string());
 
-        let res = self.http_client.get(sync_url).send().await?;
+        let res = self.send(self.http_client.get(sync_url)).await?;
 
-        Ok(res.json::<SyncMainData>().await?)
+        decode::<SyncMainData>(res)
     }
 
     // is not implemented by qBittorrent yet
     // pub async fn sync_peers_data<S: ToString>(&self, hash: S, rid: Option<usize>) -> Result<, QBittorrentError> {}
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub async fn global_transfer_info(&self) -> Result<GlobalTransferInfo, QBittorrentError> {
         let info_url = self.build_url("/api/v2/transfer/info").await?;
-        let res = self.http_client.get(info_url).send().await?;
+        let res = self.send(self.http_client.get(info_url)).await?;
 
-        Ok(res.json::<GlobalTransferInfo>().await?)
+        decode::<GlobalTransferInfo>(res)
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub async fn alternative_speed_limits_enabled(&self) -> Result<bool, QBittorrentError> {
         let limits_url = self.build_url("/api/v2/transfer/speedLimitsMode").await?;
 
-        let res = self.http_client.get(limits_url).send().await?;
-        let text = res.text().await?;
+        let res = self.send(self.http_client.get(limits_url)).await?;
+        let endpoint = res.endpoint.clone();
+        let text = res.body;
 
-        if text == "1" {
-            Ok(true)
-        } else {
-            Ok(false)
+        match text.trim() {
+            "1" => Ok(true),
+            "0" => Ok(false),
+            _ => Err(QBittorrentError::UnexpectedBody { endpoint, body: text })
         }
     }
 
+    // Toggles only when the mode differs, concurrent callers of this client are serialized
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(enabled)))]
+    pub async fn set_alternative_speed_limits(&self, enabled: bool) -> Result<(), QBittorrentError> {
+        let _guard = self.alt_speed_lock.lock().await;
+
+        if self.alternative_speed_limits_enabled().await? != enabled {
+            self.toggle_alternative_speed_limits().await?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub async fn toggle_alternative_speed_limits(&self) -> Result<(), QBittorrentError> {
         let toggle_url = self.build_url("/api/v2/transfer/toggleSpeedLimitsMode").await?;
-        self.http_client.post(toggle_url).send().await?;
+        self.send(self.http_client.post(toggle_url)).await?;
 
         Ok(())
     }
 
     // The response is the value of current global download speed limit in bytes/second; this value will be zero if no limit is applied.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub async fn download_limit(&self) -> Result<usize, QBittorrentError> {
         let limit_url = self.build_url("/api/v2/transfer/downloadLimit").await?;
-        let res = self.http_client.get(limit_url).send().await?;
-        let text = res.text().await?;
+        let res = self.send(self.http_client.get(limit_url)).await?;
+        let text = res.body;
 
         Ok(text.parse::<usize>()?)
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(limit)))]
     pub async fn set_download_limit(&self, limit: usize) -> Result<(), QBittorrentError> {
         let mut limit_url = self.build_url("/api/v2/transfer/setDownloadLimit").await?;
         limit_url.query_pairs_mut()
             .append_pair("limit", &limit.to_string());
 
-        self.http_client.post(limit_url).send().await?;
+        self.send(self.http_client.post(limit_url)).await?;
 
         Ok(())
     }
 
     // The response is the value of current global upload speed limit in bytes/second; this value will be zero if no limit is applied.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub async fn upload_limit(&self) -> Result<usize, QBittorrentError> {
         let limit_url = self.build_url("/api/v2/transfer/uploadLimit").await?;
-        let res = self.http_client.get(limit_url).send().await?;
-        let text = res.text().await?;
+        let res = self.send(self.http_client.get(limit_url)).await?;
+        let text = res.body;
 
         Ok(text.parse::<usize>()?)
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(limit)))]
     pub async fn set_upload_limit(&self, limit: usize) -> Result<(), QBittorrentError> {
         let mut limit_url = self.build_url("/api/v2/transfer/setUploadLimit").await?;
         limit_url.query_pairs_mut()
             .append_pair("limit", &limit.to_string());
 
-        self.http_client.post(limit_url).send().await?;
+        self.send(self.http_client.post(limit_url)).await?;
 
         Ok(())
     }
 
     // The peer to ban, or multiple peers separated by a pipe |. Each peer is a colon-separated host:port
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(peer_count = peers.to_string().split('|').count())))]
     pub async fn ban_peers<S: ToString>(&self, peers: S) -> Result<(), QBittorrentError> {
         let mut ban_url = self.build_url("/api/v2/transfer/banPeers").await?;
         ban_url.query_pairs_mut()
             .append_pair("peers", &peers.to_string());
 
-        let res = self.http_client.post(ban_url).send().await?;
+        self.send(self.http_client.post(ban_url)).await?;
 
-        if res.status().is_success() {
-            Ok(())
-        } else {
-            Err(QBittorrentError::BadRequest)
-        }
+        Ok(())
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(filter = %params.filter, limit = ?params.limit, offset = ?params.offset)))]
     pub async fn torrent_list(&self, params: TorrentListParams) -> Result<Vec<Torrent>, QBittorrentError> {
         let mut list_url = self.build_url("/api/v2/torrents/info").await?;
         let mut pairs = list_url.query_pairs_mut();
         pairs.append_pair("filter", &params.filter.to_string());
-        pairs.append_pair("category", &urlencoding::encode(&params.category));
-        pairs.append_pair("tag", &urlencoding::encode(&params.tag));
+
+        // append_pair encodes the values itself
+        if let Some(category) = params.category.as_param() {
+            pairs.append_pair("category", category);
+        }
+
+        if let Some(tag) = params.tag.as_param() {
+            pairs.append_pair("tag", tag);
+        }
+
         pairs.append_pair("reverse", if params.reverse { "true" } else { "false" });
         
         if let Some(sort) = params.sort {
-            pairs.append_pair("sort", &sort);
+            pairs.append_pair("sort", &sort.to_string());
         }
 
         if let Some(limit) = params.limit {
@@ -273,97 +581,252 @@ impl QBittorrentClient {
 
         drop(pairs);
 
-        let res = self.http_client.get(list_url).send().await?;
+        let res = self.send(self.http_client.get(list_url)).await?;
+
+        decode::<Vec<Torrent>>(res)
+    }
 
-        if res.status().is_success() {
-            Ok(res.json::<Vec<Torrent>>().await?)
+    // Lists the torrents page_size at a time, fetching the next page when the previous one is consumed.
+    // params.limit and params.offset are replaced by the paging. consistent sorts by added_on (oldest first), so torrents
+    // added during the scan end up on the last page instead of shifting the pages not fetched yet, and skips torrents
+    // already yielded. The stream ends after the first error
+    pub fn torrent_list_paged(&self, params: TorrentListParams, page_size: usize, consistent: bool) -> impl Stream<Item = Result<Torrent, QBittorrentError>> + '_ {
+        let params = if consistent {
+            TorrentListParams {
+                sort: Some(TorrentSortField::AddedOn),
+                reverse: false,
+                ..params
+            }
         } else {
-            Err(QBittorrentError::BadRequest)
-        }
+            params
+        };
+
+        let page_size = page_size.max(1);
+        let state = (params, 0, VecDeque::<Torrent>::new(), HashSet::new(), false);
+
+        stream::unfold(state, move |(params, mut offset, mut buffer, mut seen, mut done)| async move {
+            loop {
+                if let Some(torrent) = buffer.pop_front() {
+                    if consistent && !seen.insert(torrent.hash.clone()) {
+                        continue;
+                    }
+
+                    return Some((Ok(torrent), (params, offset, buffer, seen, done)));
+                }
+
+                if done {
+                    return None;
+                }
+
+                let page = params.clone().page(page_size, offset as isize);
+
+                match self.torrent_list(page).await {
+                    Ok(torrents) => {
+                        done = torrents.len() < page_size;
+                        offset += torrents.len();
+                        buffer.extend(torrents);
+                    }
+                    Err(err) => return Some((Err(err), (params, offset, buffer, seen, true)))
+                }
+            }
+        })
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(hash = %hash.to_string())))]
     pub async fn torrent_generic_properties<S: ToString>(&self, hash: S) -> Result<TorrentGenericProperties, QBittorrentError> {
         let mut props_url = self.build_url("/api/v2/torrents/properties").await?;
         props_url.query_pairs_mut().append_pair("hash", &hash.to_string());
 
-        let res = self.http_client.get(props_url).send().await?;
+        let res = self.send(self.http_client.get(props_url)).await?;
 
-        if res.status().is_success() {
-            Ok(res.json::<TorrentGenericProperties>().await?)
-        } else {
-            Err(QBittorrentError::BadInput("torrent hash was not found".to_string()))
-        }
+        decode::<TorrentGenericProperties>(res)
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(hash = %hash.to_string())))]
     pub async fn torrent_trackers<S: ToString>(&self, hash: S) -> Result<Vec<TorrentTracker>, QBittorrentError> {
         let mut trackers_url = self.build_url("/api/v2/torrents/trackers").await?;
         trackers_url.query_pairs_mut().append_pair("hash", &hash.to_string());
 
-        let res = self.http_client.get(trackers_url).send().await?;
+        let res = self.send(self.http_client.get(trackers_url)).await?;
 
-        if res.status().is_success() {
-            Ok(res.json::<Vec<TorrentTracker>>().await?)
-        } else {
-            Err(QBittorrentError::BadInput("torrent hash was not found".to_string()))
-        }
+        decode::<Vec<TorrentTracker>>(res)
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(hash = %hash.to_string())))]
     pub async fn torrent_web_seeds<S: ToString>(&self, hash: S) -> Result<Vec<TorrentWebSeed>, QBittorrentError> {
         let mut seeds_url = self.build_url("/api/v2/torrents/webseeds").await?;
         seeds_url.query_pairs_mut().append_pair("hash", &hash.to_string());
 
-        let res = self.http_client.get(seeds_url).send().await?;
+        let res = self.send(self.http_client.get(seeds_url)).await?;
 
-        if res.status().is_success() {
-            Ok(res.json::<Vec<TorrentWebSeed>>().await?)
-        } else {
-            Err(QBittorrentError::BadInput("torrent hash was not found".to_string()))
-        }
+        decode::<Vec<TorrentWebSeed>>(res)
     }
 
     // hashes: The hashes of the torrents you want to pause. hashes can contain multiple hashes separated by |, to pause multiple torrents, or set to all, to pause all torrents.
+    // Uses torrents/stop on qBittorrent 5.x
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(hash_count = ?hash_count(&hashes.to_string()))))]
     pub async fn torrent_pause<S: ToString>(&self, hashes: S) -> Result<(), QBittorrentError> {
-        let mut pause_url = self.build_url("/api/v2/torrents/pause").await?;
+        let endpoint = if self.capabilities().await?.start_stop_endpoints {
+            "/api/v2/torrents/stop"
+        } else {
+            "/api/v2/torrents/pause"
+        };
+
+        let mut pause_url = self.build_url(endpoint).await?;
         pause_url.query_pairs_mut().append_pair("hashes", &hashes.to_string());
 
-        let res = self.http_client.post(pause_url).send().await?;
+        self.send(self.http_client.post(pause_url)).await?;
 
-        if res.status().is_success() {
-            Ok(())
-        } else {
-            Err(QBittorrentError::BadRequest)
-        }
+        Ok(())
     }
 
-    // hashes: The hashes of the torrents you want to pause. hashes can contain multiple hashes separated by |, to pause multiple torrents, or set to all, to pause all torrents.
+    // hashes: The hashes of the torrents you want to resume. hashes can contain multiple hashes separated by |, to resume multiple torrents, or set to all, to resume all torrents.
+    // Uses torrents/start on qBittorrent 5.x
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(hash_count = ?hash_count(&hashes.to_string()))))]
     pub async fn torrent_resume<S: ToString>(&self, hashes: S) -> Result<(), QBittorrentError> {
-        let mut resume_url = self.build_url("/api/v2/torrents/resume").await?;
+        let endpoint = if self.capabilities().await?.start_stop_endpoints {
+            "/api/v2/torrents/start"
+        } else {
+            "/api/v2/torrents/resume"
+        };
+
+        let mut resume_url = self.build_url(endpoint).await?;
         resume_url.query_pairs_mut().append_pair("hashes", &hashes.to_string());
 
-        let res = self.http_client.post(resume_url).send().await?;
+        self.send(self.http_client.post(resume_url)).await?;
 
-        if res.status().is_success() {
-            Ok(())
-        } else {
-            Err(QBittorrentError::BadRequest)
-        }
+        Ok(())
     }
 
     // hashes: The hashes of the torrents you want to delete. hashes can contain multiple hashes separated by |, to delete multiple torrents, or set to all, to delete all torrents.
     // delete_files: If set to true, the downloaded data will also be deleted, otherwise has no effect.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(hash_count = ?hash_count(&hashes.to_string()), delete_files)))]
     pub async fn torrent_delete<S: ToString>(&self, hashes: S, delete_files: bool) -> Result<(), QBittorrentError> {
         let mut delete_url = self.build_url("/api/v2/torrents/delete").await?;
         delete_url.query_pairs_mut()
             .append_pair("hashes", &hashes.to_string())
             .append_pair("deleteFiles", if delete_files { "true" } else { "false" });
 
-        let res = self.http_client.post(delete_url).send().await?;
+        self.send(self.http_client.post(delete_url)).await?;
 
-        if res.status().is_success() {
-            Ok(())
-        } else {
-            Err(QBittorrentError::BadRequest)
+        Ok(())
+    }
+}
+
+// Span of a single API call (including retries and re-login), with its status code and latency
+#[cfg(feature = "tracing")]
+async fn traced<F>(method: Method, endpoint: String, request: F) -> Result<ApiResponse, QBittorrentError>
+where
+    F: Future<Output = Result<ApiResponse, QBittorrentError>>
+{
+    use tracing::{field::Empty, Instrument};
+
+    let span = tracing::debug_span!("request", %method, endpoint, status = Empty, latency_ms = Empty);
+    let started = std::time::Instant::now();
+    let res = request.instrument(span.clone()).await;
+
+    span.record("latency_ms", started.elapsed().as_millis() as u64);
+
+    match res {
+        Ok(ref res) => {
+            span.record("status", res.status.as_u16());
         }
+        Err(ref err) => {
+            if let Some(code) = err.status_code() {
+                span.record("status", code);
+            }
+
+            span.in_scope(|| tracing::warn!(error = %err, "qBittorrent request failed"));
+        }
+    }
+
+    res
+}
+
+#[cfg(feature = "tracing")]
+fn hash_count(hashes: &str) -> Option<usize> {
+    if hashes == "all" {
+        None
+    } else {
+        Some(hashes.split('|').count())
+    }
+}
+
+// A response with its body already read, so a connection dropped while the body is streaming
+// fails the attempt and is retried like any other request error
+struct ApiResponse {
+    endpoint: String,
+    status: StatusCode,
+    body: String
+}
+
+impl ApiResponse {
+    async fn read(res: Response) -> Result<Self, QBittorrentError> {
+        Ok(ApiResponse {
+            endpoint: res.url().path().to_string(),
+            status: res.status(),
+            body: res.text().await?
+        })
+    }
+}
+
+fn check_response(res: ApiResponse) -> Result<ApiResponse, QBittorrentError> {
+    let status = res.status;
+
+    if status.is_success() {
+        return Ok(res);
     }
+
+    Err(match status {
+        StatusCode::BAD_REQUEST => QBittorrentError::BadRequest,
+        StatusCode::FORBIDDEN => QBittorrentError::Forbidden,
+        StatusCode::NOT_FOUND => QBittorrentError::NotFound,
+        StatusCode::CONFLICT => QBittorrentError::Conflict,
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => QBittorrentError::UnsupportedMediaType,
+        _ => QBittorrentError::Status {
+            code: status.as_u16(),
+            body: res.body
+        }
+    })
 }
 
+fn decode<T: DeserializeOwned>(res: ApiResponse) -> Result<T, QBittorrentError> {
+    const SNIPPET_LEN: usize = 256;
+
+    serde_json::from_str(&res.body).map_err(|source| QBittorrentError::Decode {
+        endpoint: res.endpoint,
+        body_snippet: res.body.chars().take(SNIPPET_LEN).collect(),
+        source
+    })
+}
+
+// Errors are yielded without ending the stream, the next poll starts from the last seen id again
+fn tail<'a, T, F, Fut>(last_known_id: Option<usize>, interval: Duration, id: fn(&T) -> usize, fetch: F) -> impl Stream<Item = Result<T, QBittorrentError>> + 'a
+where
+    T: 'a,
+    F: Fn(Option<usize>) -> Fut + 'a,
+    Fut: Future<Output = Result<Vec<T>, QBittorrentError>> + 'a
+{
+    let state = (fetch, last_known_id, VecDeque::new(), false);
+
+    stream::unfold(state, move |(fetch, mut last_known_id, mut buffer, mut polled)| async move {
+        loop {
+            if let Some(entry) = buffer.pop_front() {
+                return Some((Ok(entry), (fetch, last_known_id, buffer, polled)));
+            }
+
+            if polled {
+                tokio::time::sleep(interval).await;
+            }
+            polled = true;
+
+            match fetch(last_known_id).await {
+                Ok(entries) => {
+                    last_known_id = entries.iter().map(id).max().or(last_known_id);
+                    buffer.extend(entries);
+                }
+                Err(err) => return Some((Err(err), (fetch, last_known_id, buffer, polled)))
+            }
+        }
+    })
+}
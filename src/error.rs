@@ -1,28 +1,85 @@
 use std::{
     fmt,
     error::Error,
+    io,
     num::ParseIntError
 };
 
 #[derive(Debug)]
 pub enum QBittorrentError {
     AuthFailed,
+    InvalidCredentials,
+    IpBanned,
     BadRequest,
+    Forbidden,
+    NotFound,
+    Conflict,
+    UnsupportedMediaType,
+    Status {
+        code: u16,
+        body: String
+    },
+    UnexpectedBody {
+        endpoint: String,
+        body: String
+    },
+    Decode {
+        endpoint: String,
+        body_snippet: String,
+        source: serde_json::Error
+    },
     ParseInt(ParseIntError),
     BadInput(String),
     Url(url::ParseError),
-    Reqwest(reqwest::Error)
+    Reqwest(reqwest::Error),
+    Io(io::Error),
+    Json(serde_json::Error),
+    #[cfg(feature = "toml")]
+    TomlSerialize(toml::ser::Error),
+    #[cfg(feature = "toml")]
+    TomlDeserialize(toml::de::Error)
+}
+
+impl QBittorrentError {
+    // HTTP status code of the response which caused the error
+    pub fn status_code(&self) -> Option<u16> {
+        match *self {
+            QBittorrentError::BadRequest => Some(400),
+            QBittorrentError::Forbidden => Some(403),
+            QBittorrentError::NotFound => Some(404),
+            QBittorrentError::Conflict => Some(409),
+            QBittorrentError::UnsupportedMediaType => Some(415),
+            QBittorrentError::Status { code, .. } => Some(code),
+            QBittorrentError::Reqwest(ref err) => err.status().map(|status| status.as_u16()),
+            _ => None
+        }
+    }
 }
 
 impl fmt::Display for QBittorrentError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             QBittorrentError::AuthFailed => write!(f, "failed to log in"),
+            QBittorrentError::InvalidCredentials => write!(f, "failed to log in: invalid username or password"),
+            QBittorrentError::IpBanned => write!(f, "failed to log in: ip address is banned after too many failed attempts"),
             QBittorrentError::BadRequest => write!(f, "bad request http error occured"),
+            QBittorrentError::Forbidden => write!(f, "forbidden http error occured (not logged in?)"),
+            QBittorrentError::NotFound => write!(f, "not found http error occured"),
+            QBittorrentError::Conflict => write!(f, "conflict http error occured"),
+            QBittorrentError::UnsupportedMediaType => write!(f, "unsupported media type http error occured"),
+            QBittorrentError::Status { code, ref body } => write!(f, "unexpected http status {}: {}", code, body),
+            QBittorrentError::UnexpectedBody { ref endpoint, ref body } => write!(f, "unexpected response of {}: {}", endpoint, body),
+            QBittorrentError::Decode { ref endpoint, ref body_snippet, ref source } => write!(f, "failed to decode response of {}: {} (body: {})", endpoint, source, body_snippet),
             QBittorrentError::ParseInt(ref err) => write!(f, "failed to parse integer: {}", err),
             QBittorrentError::BadInput(ref err) => write!(f, "bad input error occured: {}", err),
             QBittorrentError::Url(ref err) => write!(f, "url error occured: {}", err),
-            QBittorrentError::Reqwest(ref err) => write!(f, "reqwest error occured: {}", err)
+            QBittorrentError::Reqwest(ref err) => write!(f, "reqwest error occured: {}", err),
+            QBittorrentError::Io(ref err) => write!(f, "io error occured: {}", err),
+            QBittorrentError::Json(ref err) => write!(f, "json error occured: {}", err),
+            #[cfg(feature = "toml")]
+            QBittorrentError::TomlSerialize(ref err) => write!(f, "failed to serialize toml: {}", err),
+            #[cfg(feature = "toml")]
+            QBittorrentError::TomlDeserialize(ref err) => write!(f, "failed to parse toml: {}", err)
         }
     }
 }
@@ -31,11 +88,26 @@ impl Error for QBittorrentError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match *self {
             QBittorrentError::AuthFailed => None,
+            QBittorrentError::InvalidCredentials => None,
+            QBittorrentError::IpBanned => None,
             QBittorrentError::BadRequest => None,
+            QBittorrentError::Forbidden => None,
+            QBittorrentError::NotFound => None,
+            QBittorrentError::Conflict => None,
+            QBittorrentError::UnsupportedMediaType => None,
+            QBittorrentError::Status { .. } => None,
+            QBittorrentError::UnexpectedBody { .. } => None,
+            QBittorrentError::Decode { ref source, .. } => Some(source),
             QBittorrentError::ParseInt(ref err) => Some(err),
             QBittorrentError::BadInput(..) => None,
             QBittorrentError::Url(ref err) => Some(err),
-            QBittorrentError::Reqwest(ref err) => Some(err)
+            QBittorrentError::Reqwest(ref err) => Some(err),
+            QBittorrentError::Io(ref err) => Some(err),
+            QBittorrentError::Json(ref err) => Some(err),
+            #[cfg(feature = "toml")]
+            QBittorrentError::TomlSerialize(ref err) => Some(err),
+            #[cfg(feature = "toml")]
+            QBittorrentError::TomlDeserialize(ref err) => Some(err)
         }
     }
 }
@@ -58,3 +130,28 @@ impl From<reqwest::Error> for QBittorrentError {
     }
 }
 
+impl From<io::Error> for QBittorrentError {
+    fn from(err: io::Error) -> QBittorrentError {
+        QBittorrentError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for QBittorrentError {
+    fn from(err: serde_json::Error) -> QBittorrentError {
+        QBittorrentError::Json(err)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::ser::Error> for QBittorrentError {
+    fn from(err: toml::ser::Error) -> QBittorrentError {
+        QBittorrentError::TomlSerialize(err)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::de::Error> for QBittorrentError {
+    fn from(err: toml::de::Error) -> QBittorrentError {
+        QBittorrentError::TomlDeserialize(err)
+    }
+}
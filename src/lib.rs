@@ -1,8 +1,27 @@
+#[cfg(feature = "chrono")]
+mod bandwidth;
 mod error;
 mod client;
+#[cfg(any(feature = "log-bridge", feature = "tracing-bridge"))]
+mod log_bridge;
+mod preferences;
+mod retry;
+mod schedule;
+mod sentinel;
+mod version;
 pub mod models;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "chrono")]
+pub use bandwidth::{BandwidthScheduler, SpeedLimits, TimeWindow, EVERY_DAY, WEEKDAYS, WEEKEND};
 pub use error::QBittorrentError;
-pub use client::QBittorrentClient;
+pub use client::{QBittorrentClient, ProxyAuth};
+#[cfg(any(feature = "log-bridge", feature = "tracing-bridge"))]
+pub use log_bridge::{forward_logs, forward_peer_logs};
+pub use preferences::{PreferenceChange, SECRET_PREFERENCES};
+pub use retry::RetryPolicy;
+pub use schedule::{SpeedSchedule, TimeOfDay};
+pub use version::{ApiVersion, AppVersion, Capabilities};
 
 /*pub fn add(left: usize, right: usize) -> usize {
     left + right
@@ -0,0 +1,248 @@
+use std::{
+    pin::pin,
+    time::Duration
+};
+
+use futures_util::StreamExt;
+
+use crate::{
+    models::{Log, LogLevel, LogParams, PeerLog},
+    QBittorrentClient
+};
+
+// Entries are emitted with target "qbittorrent" through log (log-bridge feature) and/or tracing (tracing-bridge feature).
+// Both functions run forever, failed polls are reported as warnings of this module.
+
+pub async fn forward_logs(client: &QBittorrentClient, params: LogParams, interval: Duration) {
+    let mut logs = pin!(client.tail_logs(params, interval));
+
+    while let Some(log) = logs.next().await {
+        match log {
+            Ok(log) => emit_log(&log),
+            Err(err) => emit_error(&err)
+        }
+    }
+}
+
+pub async fn forward_peer_logs(client: &QBittorrentClient, last_known_id: Option<usize>, interval: Duration) {
+    let mut logs = pin!(client.tail_peer_logs(last_known_id, interval));
+
+    while let Some(log) = logs.next().await {
+        match log {
+            Ok(log) => emit_peer_log(&log),
+            Err(err) => emit_error(&err)
+        }
+    }
+}
+
+fn emit_log(log: &Log) {
+    #[cfg(feature = "tracing-bridge")]
+    match log.log_type {
+        // unknown types of newer servers are logged as info
+        LogLevel::Normal | LogLevel::Info | LogLevel::Other(_) => tracing::info!(target: "qbittorrent", id = log.id, log_type = ?log.log_type, "{}", log.message),
+        LogLevel::Warning => tracing::warn!(target: "qbittorrent", id = log.id, log_type = ?log.log_type, "{}", log.message),
+        LogLevel::Critical => tracing::error!(target: "qbittorrent", id = log.id, log_type = ?log.log_type, "{}", log.message)
+    }
+
+    #[cfg(feature = "log-bridge")]
+    {
+        let level = match log.log_type {
+            LogLevel::Normal | LogLevel::Info | LogLevel::Other(_) => log::Level::Info,
+            LogLevel::Warning => log::Level::Warn,
+            LogLevel::Critical => log::Level::Error
+        };
+
+        log::log!(target: "qbittorrent", level, id = log.id; "{}", log.message);
+    }
+}
+
+fn emit_peer_log(log: &PeerLog) {
+    #[cfg(feature = "tracing-bridge")]
+    if log.blocked {
+        tracing::warn!(target: "qbittorrent", id = log.id, ip = %log.ip, blocked = log.blocked, reason = %log.reason, "peer blocked");
+    } else {
+        tracing::info!(target: "qbittorrent", id = log.id, ip = %log.ip, blocked = log.blocked, reason = %log.reason, "peer banned");
+    }
+
+    #[cfg(feature = "log-bridge")]
+    {
+        let (level, message) = if log.blocked {
+            (log::Level::Warn, "peer blocked")
+        } else {
+            (log::Level::Info, "peer banned")
+        };
+
+        log::log!(target: "qbittorrent", level, id = log.id, ip = log.ip.as_str(), blocked = log.blocked, reason = log.reason.as_str(); "{}", message);
+    }
+}
+
+fn emit_error(err: &crate::QBittorrentError) {
+    #[cfg(feature = "tracing-bridge")]
+    tracing::warn!("failed to fetch qBittorrent logs: {}", err);
+
+    #[cfg(feature = "log-bridge")]
+    log::warn!("failed to fetch qBittorrent logs: {}", err);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (level, message) of the emitted records, captured on the current thread
+    type Records = Vec<(String, String)>;
+
+    #[cfg(feature = "tracing-bridge")]
+    mod tracing_capture {
+        use std::{
+            fmt,
+            sync::{Arc, Mutex}
+        };
+
+        use tracing::{
+            field::{Field, Visit},
+            span, Event, Metadata, Subscriber
+        };
+
+        use super::Records;
+
+        #[derive(Default)]
+        struct Capture(Arc<Mutex<Records>>);
+
+        struct Message(String);
+
+        impl Visit for Message {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                if field.name() == "message" {
+                    self.0 = format!("{:?}", value);
+                }
+            }
+        }
+
+        impl Subscriber for Capture {
+            fn enabled(&self, _: &Metadata) -> bool {
+                true
+            }
+
+            fn new_span(&self, _: &span::Attributes) -> span::Id {
+                span::Id::from_u64(1)
+            }
+
+            fn record(&self, _: &span::Id, _: &span::Record) {}
+
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+            fn event(&self, event: &Event) {
+                let mut message = Message(String::new());
+                event.record(&mut message);
+                self.0.lock().unwrap().push((event.metadata().level().to_string(), message.0));
+            }
+
+            fn enter(&self, _: &span::Id) {}
+
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        pub fn captured(emit: impl FnOnce()) -> Records {
+            let capture = Capture::default();
+            let records = capture.0.clone();
+            tracing::subscriber::with_default(capture, emit);
+
+            let records = records.lock().unwrap().clone();
+            records
+        }
+    }
+
+    #[cfg(feature = "log-bridge")]
+    mod log_capture {
+        use std::{cell::RefCell, sync::Once};
+
+        use log::{Metadata, Record};
+
+        use super::Records;
+
+        thread_local! {
+            static RECORDS: RefCell<Records> = const { RefCell::new(Vec::new()) };
+        }
+
+        // the logger is global, the records of each test thread are kept apart
+        struct Capture;
+
+        impl log::Log for Capture {
+            fn enabled(&self, _: &Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &Record) {
+                RECORDS.with_borrow_mut(|records| records.push((record.level().to_string(), record.args().to_string())));
+            }
+
+            fn flush(&self) {}
+        }
+
+        pub fn captured(emit: impl FnOnce()) -> Records {
+            static INIT: Once = Once::new();
+
+            INIT.call_once(|| {
+                log::set_logger(&Capture).unwrap();
+                log::set_max_level(log::LevelFilter::Trace);
+            });
+
+            RECORDS.with_borrow_mut(Vec::clear);
+            emit();
+            RECORDS.take()
+        }
+    }
+
+    fn assert_emitted(emit: impl Fn(), level: &str, message: &str) {
+        let expected = vec![(level.to_string(), message.to_string())];
+
+        #[cfg(feature = "tracing-bridge")]
+        assert_eq!(tracing_capture::captured(&emit), expected);
+
+        #[cfg(feature = "log-bridge")]
+        assert_eq!(log_capture::captured(&emit), expected);
+    }
+
+    #[test]
+    fn maps_log_levels() {
+        let levels = [
+            (LogLevel::Normal, "INFO"),
+            (LogLevel::Info, "INFO"),
+            (LogLevel::Warning, "WARN"),
+            (LogLevel::Critical, "ERROR"),
+            (LogLevel::Other(16), "INFO")
+        ];
+
+        for (log_type, level) in levels {
+            let log = Log {
+                id: 1,
+                message: format!("{:?} message", log_type),
+                timestamp: 1718000000,
+                log_type
+            };
+
+            assert_emitted(|| emit_log(&log), level, &log.message);
+        }
+    }
+
+    #[test]
+    fn maps_peer_logs() {
+        for (blocked, level, message) in [(true, "WARN", "peer blocked"), (false, "INFO", "peer banned")] {
+            let log = PeerLog {
+                id: 1,
+                ip: "10.0.0.1".to_string(),
+                timestamp: 1718000000,
+                blocked,
+                reason: "IP filter".to_string()
+            };
+
+            assert_emitted(|| emit_peer_log(&log), level, message);
+        }
+    }
+
+    #[test]
+    fn reports_errors_as_warnings() {
+        let err = crate::QBittorrentError::BadRequest;
+        assert_emitted(|| emit_error(&err), "WARN", &format!("failed to fetch qBittorrent logs: {}", err));
+    }
+}
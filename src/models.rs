@@ -1,169 +1,376 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH}
+};
 
-use serde::{Deserialize, Deserializer};
-use serde_json::Value;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
 
-#[derive(Debug, Clone, Deserialize)]
+use crate::{
+    sentinel,
+    version::AppVersion
+};
+
+// Unix time in seconds
+pub type Timestamp = i64;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct AppBuildInfo {
     pub qt: String,
     pub libtorrent: String,
     pub boost: String,
     pub openssl: String,
-    pub bitness: usize
+    pub bitness: u8,
+    // reported by qBittorrent 4.6 and later
+    pub platform: Option<String>,
+    pub zlib: Option<String>,
+    // not part of the buildInfo response, filled in from /api/v2/app/version by build_info()
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<AppVersion>
 }
 
-#[derive(Debug, Clone, Deserialize)]
+// Every field is optional, so the same struct is used to change preferences:
+// only fields set to Some(..) are serialized
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(remote = "Self")]
 pub struct AppPreferences {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub locale: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub create_subfolder_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub start_paused_enabled: Option<bool>,
     // not implemented yet (by qBittorrent): pub auto_delete_mode: isize(?),
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub preallocate_all: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub incomplete_files_ext: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub auto_tmm_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub torrent_changed_tmm_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub save_path_changed_tmm_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub category_changed_tmm_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub save_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub temp_path_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub temp_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub scan_dirs: Option<HashMap<String, IntOrString>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub export_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub export_dir_fin: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub mail_notification_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub mail_notification_sender: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub mail_notification_email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub mail_notification_smtp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub mail_notification_ssl_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub mail_notification_auth_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub mail_notification_username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub mail_notification_password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub autorun_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub autorun_program: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub queueing_enabled: Option<bool>,
-    pub max_active_downloads: Option<usize>,
-    pub max_active_torrents: Option<usize>,
-    pub max_active_uploads: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_active_downloads: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_active_torrents: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_active_uploads: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub dont_count_slow_torrents: Option<bool>,
-    pub slow_torrent_dl_rate_threshold: Option<usize>,
-    pub slow_torrent_ul_rate_threshold: Option<usize>,
-    pub slow_torrent_inactive_timer: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slow_torrent_dl_rate_threshold: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slow_torrent_ul_rate_threshold: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slow_torrent_inactive_timer: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_ratio_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_ratio: Option<f64>,
-    pub max_ratio_act: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_ratio_act: Option<MaxRatioAction>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub listen_port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub upnp: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub random_port: Option<bool>,
-    pub dl_limit: Option<isize>,
-    pub up_limit: Option<isize>,
-    pub max_connec: Option<usize>,
-    pub max_connec_per_torrent: Option<usize>,
-    pub max_uploads: Option<usize>,
-    pub max_uploads_per_torrent: Option<usize>,
-    pub stop_tracker_timeout: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dl_limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub up_limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_connec: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_connec_per_torrent: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_uploads: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_uploads_per_torrent: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_tracker_timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub enable_piece_extent_affinity: Option<bool>,
-    pub bittorrent_protocol: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bittorrent_protocol: Option<BittorrentProtocol>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub limit_utp_rate: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub limit_tcp_overhead: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub limit_lan_peers: Option<bool>,
-    pub alt_dl_limit: Option<usize>,
-    pub alt_up_limit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alt_dl_limit: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alt_up_limit: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub scheduler_enabled: Option<bool>,
-    pub schedule_from_hour: Option<usize>,
-    pub schedule_from_min: Option<usize>,
-    pub schedule_to_hour: Option<usize>,
-    pub schedule_to_min: Option<usize>,
-    pub scheduler_days: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule_from_hour: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule_from_min: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule_to_hour: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule_to_min: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduler_days: Option<SchedulerDays>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub dht: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub pex: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub lsd: Option<bool>,
-    pub encryption: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub anonymous_mode: Option<bool>,
-    pub proxy_type: Option<IntOrString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_type: Option<ProxyType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub proxy_ip: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub proxy_port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub proxy_peer_connections: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub proxy_auth_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub proxy_username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub proxy_password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub proxy_torrents_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ip_filter_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ip_filter_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ip_filter_trackers: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_ui_domain_list: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_ui_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_ui_port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_ui_upnp: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_ui_username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_ui_password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_ui_csrf_protection_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_ui_clickjacking_protection_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_ui_secure_cookie_enabled: Option<bool>,
-    pub web_ui_max_auth_fail_count: Option<usize>,
-    pub web_ui_ban_duration: Option<usize>,
-    pub web_ui_session_timeout: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web_ui_max_auth_fail_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web_ui_ban_duration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web_ui_session_timeout: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_ui_host_header_validation_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub bypass_local_auth: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub bypass_auth_subnet_whitelist_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub bypass_auth_subnet_whitelist: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub alternative_webui_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub alternative_webui_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub use_https: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ssl_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ssl_cert: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_ui_https_key_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_ui_https_cert_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub dyndns_enabled: Option<bool>,
-    pub dyndns_service: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dyndns_service: Option<DynDnsService>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub dyndns_username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub dyndns_password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub dyndns_domain: Option<String>,
-    pub rss_refresh_interval: Option<usize>,
-    pub rss_max_articles_per_feed: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rss_refresh_interval: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rss_max_articles_per_feed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub rss_processing_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub rss_auto_downloading_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub rss_download_repack_proper_episodes: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub rss_smart_episode_filters: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub add_trackers_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub add_trackers: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_ui_use_custom_http_headers_enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub web_ui_custom_http_headers: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_seeding_time_enabled: Option<bool>,
-    pub max_seeding_time: Option<isize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_seeding_time: Option<i64>,
     // not implemented yet (by qBittorrent): pub announce_ip: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub announce_to_all_tiers: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub announce_to_all_trackers: Option<bool>,
-    pub async_io_threads: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub async_io_threads: Option<u64>,
     #[serde(rename = "banned_IPs")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub banned_ips: Option<String>,
-    pub checking_memory_use: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checking_memory_use: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub current_interface_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub current_network_interface: Option<String>,
-    pub disk_cache: Option<isize>,
-    pub disk_cache_ttl: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_cache: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_cache_ttl: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub embedded_tracker_port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub enable_coalesce_read_write: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub enable_embedded_tracker: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub enable_multi_connections_from_same_ip: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub enable_os_cache: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub enable_upload_suggestions: Option<bool>,
-    pub file_pool_size: Option<usize>,
-    pub outgoing_ports_max: Option<usize>,
-    pub outgoing_ports_min: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_pool_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outgoing_ports_max: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outgoing_ports_min: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub recheck_completed_torrents: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub resolve_peer_countries: Option<bool>,
-    pub save_resume_data_interval: Option<usize>,
-    pub send_buffer_low_watermark: Option<usize>,
-    pub send_buffer_watermark: Option<usize>,
-    pub send_buffer_watermark_factor: Option<usize>,
-    pub socket_backlog_size: Option<usize>,
-    pub upload_choking_algorithm: Option<usize>,
-    pub upload_slots_behavior: Option<usize>,
-    pub upnp_lease_duration: Option<usize>,
-    pub utp_tcp_mixed_mode: Option<usize>,
-}
-
-#[derive(Debug, Clone)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub save_resume_data_interval: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub send_buffer_low_watermark: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub send_buffer_watermark: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub send_buffer_watermark_factor: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket_backlog_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload_choking_algorithm: Option<UploadChokingAlgorithm>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload_slots_behavior: Option<UploadSlotsBehavior>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upnp_lease_duration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utp_tcp_mixed_mode: Option<UtpTcpMixedMode>,
+    // preferences unknown to this crate or whose type has changed, written back as they are
+    #[serde(flatten)]
+    pub extra: Map<String, Value>
+}
+
+impl<'de> Deserialize<'de> for AppPreferences {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let mut map = Map::deserialize(deserializer)?;
+
+        // qBittorrent changes the type of a preference from time to time (e.g. proxy_type int -> string),
+        // such keys are moved to extra instead of failing the whole response
+        let mismatched_keys = map.iter()
+            .filter(|(key, value)| {
+                let single = Map::from_iter([(key.to_string(), (*value).clone())]);
+                AppPreferences::deserialize(Value::Object(single)).is_err()
+            })
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+
+        let mismatched = mismatched_keys.into_iter()
+            .filter_map(|key| map.remove_entry(&key))
+            .collect::<Map<_, _>>();
+
+        let mut prefs = AppPreferences::deserialize(Value::Object(map)).map_err(D::Error::custom)?;
+        prefs.extra.extend(mismatched);
+
+        Ok(prefs)
+    }
+}
+
+impl Serialize for AppPreferences {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        AppPreferences::serialize(self, serializer)
+    }
+}
+
+pub type AppPreferencesPatch = AppPreferences;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum IntOrString {
     Int(i64),
     Str(String)
@@ -189,8 +396,216 @@ impl<'de> Deserialize<'de> for IntOrString {
     }
 }
 
+impl Serialize for IntOrString {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            IntOrString::Int(i) => serializer.serialize_i64(i),
+            IntOrString::Str(ref s) => serializer.serialize_str(s)
+        }
+    }
+}
+
+// Share limit of a torrent: -2 uses the global limit and -1 (or any other negative value) means no limit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShareLimit<T> {
+    Global,
+    Unlimited,
+    Limit(T)
+}
+
+macro_rules! share_limit_serde {
+    ($ty:ty) => {
+        impl<'de> Deserialize<'de> for ShareLimit<$ty> {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                let value = f64::deserialize(deserializer)?;
+
+                Ok(match value {
+                    -2.0 => ShareLimit::Global,
+                    value if value < 0.0 => ShareLimit::Unlimited,
+                    value => ShareLimit::Limit(value as $ty)
+                })
+            }
+        }
+
+        impl Serialize for ShareLimit<$ty> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                match *self {
+                    ShareLimit::Global => serializer.serialize_i64(-2),
+                    ShareLimit::Unlimited => serializer.serialize_i64(-1),
+                    ShareLimit::Limit(limit) => limit.serialize(serializer)
+                }
+            }
+        }
+    };
+}
+
+share_limit_serde!(f64);
+share_limit_serde!(u64);
+
+// Numeric preference codes, unknown codes are kept in Other
+macro_rules! preference_enum {
+    ($name:ident { $($variant:ident = $code:literal),* $(,)? }) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+        #[serde(from = "i64", into = "i64")]
+        pub enum $name {
+            $($variant,)*
+            Other(i64)
+        }
+
+        impl From<i64> for $name {
+            fn from(code: i64) -> Self {
+                match code {
+                    $($code => $name::$variant,)*
+                    code => $name::Other(code)
+                }
+            }
+        }
+
+        impl From<$name> for i64 {
+            fn from(value: $name) -> i64 {
+                match value {
+                    $($name::$variant => $code,)*
+                    $name::Other(code) => code
+                }
+            }
+        }
+    };
+}
+
+preference_enum!(MaxRatioAction {
+    Pause = 0,
+    Remove = 1,
+    EnableSuperSeeding = 2,
+    RemoveWithFiles = 3
+});
+
+preference_enum!(BittorrentProtocol {
+    TcpAndUtp = 0,
+    Tcp = 1,
+    Utp = 2
+});
+
+preference_enum!(EncryptionMode {
+    Prefer = 0,
+    ForceOn = 1,
+    ForceOff = 2
+});
+
+preference_enum!(SchedulerDays {
+    Every = 0,
+    Weekdays = 1,
+    Weekends = 2,
+    Monday = 3,
+    Tuesday = 4,
+    Wednesday = 5,
+    Thursday = 6,
+    Friday = 7,
+    Saturday = 8,
+    Sunday = 9
+});
+
+preference_enum!(DynDnsService {
+    DynDns = 0,
+    NoIp = 1
+});
+
+preference_enum!(UploadChokingAlgorithm {
+    RoundRobin = 0,
+    FastestUpload = 1,
+    AntiLeech = 2
+});
+
+preference_enum!(UploadSlotsBehavior {
+    FixedSlots = 0,
+    UploadRateBased = 1
+});
+
+preference_enum!(UtpTcpMixedMode {
+    PreferTcp = 0,
+    PeerProportional = 1
+});
+
+// qBittorrent 4.6 and later use strings, older versions integer codes (-1/0 none, 1 HTTP, 2 SOCKS5,
+// 3 HTTP with authentication, 4 SOCKS5 with authentication, 5 SOCKS4).
+// Integer codes are kept as Legacy, so values are written back in the form the server uses
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "IntOrString", into = "IntOrString")]
+pub enum ProxyType {
+    None,
+    Http,
+    Socks4,
+    Socks5,
+    Other(String),
+    Legacy(i64)
+}
+
+impl ProxyType {
+    // The proxy kind in the string form, e.g. Legacy(3) -> Http
+    pub fn kind(&self) -> ProxyType {
+        match *self {
+            ProxyType::Legacy(-1 | 0) => ProxyType::None,
+            ProxyType::Legacy(1 | 3) => ProxyType::Http,
+            ProxyType::Legacy(2 | 4) => ProxyType::Socks5,
+            ProxyType::Legacy(5) => ProxyType::Socks4,
+            ProxyType::Legacy(code) => ProxyType::Other(code.to_string()),
+            ref proxy_type => proxy_type.clone()
+        }
+    }
+
+    // Whether a legacy code enables proxy authentication, None for the string form (see proxy_auth_enabled)
+    pub fn legacy_auth(&self) -> Option<bool> {
+        match *self {
+            ProxyType::Legacy(code) => Some(matches!(code, 3 | 4)),
+            _ => None
+        }
+    }
+}
+
+impl From<IntOrString> for ProxyType {
+    fn from(value: IntOrString) -> Self {
+        match value {
+            IntOrString::Int(code) => ProxyType::Legacy(code),
+            IntOrString::Str(s) => match s.as_str() {
+                "None" => ProxyType::None,
+                "HTTP" => ProxyType::Http,
+                "SOCKS4" => ProxyType::Socks4,
+                "SOCKS5" => ProxyType::Socks5,
+                _ => ProxyType::Other(s)
+            }
+        }
+    }
+}
+
+impl From<ProxyType> for IntOrString {
+    fn from(value: ProxyType) -> IntOrString {
+        match value {
+            ProxyType::None => IntOrString::Str(String::from("None")),
+            ProxyType::Http => IntOrString::Str(String::from("HTTP")),
+            ProxyType::Socks4 => IntOrString::Str(String::from("SOCKS4")),
+            ProxyType::Socks5 => IntOrString::Str(String::from("SOCKS5")),
+            ProxyType::Other(s) => IntOrString::Str(s),
+            ProxyType::Legacy(code) => IntOrString::Int(code)
+        }
+    }
+}
+
 pub struct JsonObject(pub(crate) Value);
 
+impl From<AppPreferencesPatch> for JsonObject {
+    fn from(patch: AppPreferencesPatch) -> Self {
+        JsonObject(Value::Object(patch.to_json_map()))
+    }
+}
+
 impl TryFrom<Value> for JsonObject {
     type Error = std::io::Error;
 
@@ -203,16 +618,75 @@ impl TryFrom<Value> for JsonObject {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Log {
     pub id: usize,
     pub message: String,
-    pub timestamp: usize,
+    pub timestamp: i64,
     #[serde(rename = "type")]
-    pub log_type: usize
+    pub log_type: LogLevel
+}
+
+impl Log {
+    pub fn time(&self) -> SystemTime {
+        log_time(self.timestamp)
+    }
+}
+
+// Types added by newer servers are kept as Other instead of failing the whole log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(from = "u8", into = "u8")]
+pub enum LogLevel {
+    Normal,
+    Info,
+    Warning,
+    Critical,
+    Other(u8)
+}
+
+impl From<u8> for LogLevel {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => LogLevel::Normal,
+            2 => LogLevel::Info,
+            4 => LogLevel::Warning,
+            8 => LogLevel::Critical,
+            value => LogLevel::Other(value)
+        }
+    }
+}
+
+impl From<LogLevel> for u8 {
+    fn from(level: LogLevel) -> u8 {
+        match level {
+            LogLevel::Normal => 1,
+            LogLevel::Info => 2,
+            LogLevel::Warning => 4,
+            LogLevel::Critical => 8,
+            LogLevel::Other(value) => value
+        }
+    }
+}
+
+// qBittorrent before 4.5 reported log timestamps in milliseconds
+fn log_time(timestamp: i64) -> SystemTime {
+    const MILLIS_THRESHOLD: u64 = 100_000_000_000;
+
+    let timestamp = timestamp.max(0) as u64;
+
+    if timestamp >= MILLIS_THRESHOLD {
+        UNIX_EPOCH + Duration::from_millis(timestamp)
+    } else {
+        UNIX_EPOCH + Duration::from_secs(timestamp)
+    }
+}
+
+// The time types of chrono (DateTime<Utc>) and time (OffsetDateTime) convert from SystemTime with From
+fn unix_time(timestamp: Timestamp) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(timestamp.max(0) as u64)
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct LogParams {
     pub normal: bool,
     pub info: bool,
@@ -233,103 +707,293 @@ impl Default for LogParams {
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct PeerLog {
     pub id: usize,
     pub ip: String,
-    pub timestamp: usize,
+    pub timestamp: i64,
     pub blocked: bool,
     pub reason: String
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl PeerLog {
+    pub fn time(&self) -> SystemTime {
+        log_time(self.timestamp)
+    }
+}
+
+// Keys without changes are omitted, and except for full updates torrents, categories and server_state
+// only contain the fields that changed since rid
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct SyncMainData {
     pub rid: usize,
+    #[serde(default)]
     pub full_update: bool,
-    pub torrents: HashMap<String, Torrent>,
+    #[serde(default)]
+    pub torrents: HashMap<String, PartialTorrent>,
+    #[serde(default)]
     pub torrents_removed: Vec<String>,
-    pub categories: HashMap<String, Category>,
+    #[serde(default)]
+    pub categories: HashMap<String, PartialCategory>,
+    #[serde(default)]
     pub categories_removed: Vec<String>,
+    #[serde(default)]
     pub tags: Vec<String>,
+    #[serde(default)]
     pub tags_removed: Vec<String>,
-    pub server_state: GlobalTransferInfo
+    #[serde(default)]
+    pub server_state: PartialServerState
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct PartialCategory {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "savePath", skip_serializing_if = "Option::is_none")]
+    pub save_path: Option<String>
+}
+
+// GlobalTransferInfo and the transfer settings, as sent in sync/maindata
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct PartialServerState {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dl_info_speed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dl_info_data: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub up_info_speed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub up_info_data: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dl_rate_limit: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub up_rate_limit: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dht_nodes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_alt_speed_limits: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queueing: Option<bool>,
+    // milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_interval: Option<u64>
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct GlobalTransferInfo {
-    pub dl_info_speed: usize,
-    pub dl_info_data: usize,
-    pub up_info_speed: usize,
-    pub up_info_data: usize,
-    pub dl_rate_limit: usize,
-    pub up_rate_limit: usize,
-    pub dht_nodes: usize,
+    pub dl_info_speed: u64,
+    pub dl_info_data: u64,
+    pub up_info_speed: u64,
+    pub up_info_data: u64,
+    pub dl_rate_limit: u64,
+    pub up_rate_limit: u64,
+    pub dht_nodes: u64,
     pub connection_status: String
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Category {
     pub name: String,
     #[serde(rename = "savePath")]
     pub save_path: String
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Torrent {
-    pub added_on: usize,
-    pub amount_left: usize,
+    pub added_on: Timestamp,
+    pub amount_left: u64,
     pub auto_tmm: bool,
-    pub availability: f64,
+    // None while not downloading; reported by qBittorrent 4.2 and later
+    #[serde(default, with = "sentinel::non_negative_f64")]
+    pub availability: Option<f64>,
     pub category: String,
-    pub completed: usize,
-    pub completion_on: usize,
-    pub content_path: String,
-    pub dl_limit: isize,
-    pub dlspeed: usize,
-    pub downloaded: usize,
-    pub downloaded_session: usize,
-    pub eta: usize,
+    pub completed: u64,
+    // None if not completed yet
+    #[serde(default, with = "sentinel::timestamp")]
+    pub completion_on: Option<Timestamp>,
+    // reported by qBittorrent 4.3.2 and later
+    pub content_path: Option<String>,
+    // bytes/second, None if unlimited
+    #[serde(default, with = "sentinel::positive")]
+    pub dl_limit: Option<u64>,
+    pub dlspeed: u64,
+    pub downloaded: u64,
+    pub downloaded_session: u64,
+    // seconds, None if the torrent is not expected to finish
+    #[serde(default, with = "sentinel::eta")]
+    pub eta: Option<u64>,
     pub f_l_piece_prio: bool,
     pub force_start: bool,
     pub hash: String,
-    #[serde(rename = "isPrivate")]
-    pub is_private: bool,
-    pub last_activity: usize,
+    // reported by qBittorrent 5.0 and later
+    #[serde(rename = "private", alias = "isPrivate")]
+    pub is_private: Option<bool>,
+    #[serde(default, with = "sentinel::timestamp_or_zero")]
+    pub last_activity: Option<Timestamp>,
     pub magnet_uri: String,
-    pub max_ratio: f64,
-    pub max_seeding_time: usize,
+    pub max_ratio: ShareLimit<f64>,
+    // minutes
+    pub max_seeding_time: ShareLimit<u64>,
     pub name: String,
-    pub num_complete: usize,
-    pub num_incomplete: usize,
-    pub num_leechs: usize,
-    pub num_seeds: usize,
-    pub priority: isize,
+    pub num_complete: u64,
+    pub num_incomplete: u64,
+    pub num_leechs: u64,
+    pub num_seeds: u64,
+    // queue position, None if not queued
+    #[serde(default, with = "sentinel::positive")]
+    pub priority: Option<u64>,
     pub progress: f64,
     pub ratio: f64,
     // not implemented yet (by qBittorrent): pub ratio_limit: f64,
     pub save_path: String,
-    pub seeding_time: usize,
+    // seconds; reported by qBittorrent 4.4 and later
+    pub seeding_time: Option<u64>,
     // not implemented yet (by qBittorrent): pub seeding_time_limit: isize,
-    pub seen_complete: usize,
+    #[serde(default, with = "sentinel::timestamp")]
+    pub seen_complete: Option<Timestamp>,
     pub seq_dl: bool,
-    pub size: usize,
+    pub size: u64,
     pub state: String,
     pub super_seeding: bool,
     pub tags: String,
-    pub time_active: usize,
+    // seconds
+    pub time_active: u64,
     pub tracker: String,
-    pub up_limit: isize,
-    pub uploaded: usize,
-    pub uploaded_session: usize,
-    pub upspeed: usize
+    #[serde(default, with = "sentinel::positive")]
+    pub up_limit: Option<u64>,
+    pub uploaded: u64,
+    pub uploaded_session: u64,
+    pub upspeed: u64
+}
+
+impl Torrent {
+    pub fn added_on_time(&self) -> SystemTime {
+        unix_time(self.added_on)
+    }
+
+    pub fn completion_on_time(&self) -> Option<SystemTime> {
+        self.completion_on.map(unix_time)
+    }
+
+    pub fn last_activity_time(&self) -> Option<SystemTime> {
+        self.last_activity.map(unix_time)
+    }
+
+    pub fn seen_complete_time(&self) -> Option<SystemTime> {
+        self.seen_complete.map(unix_time)
+    }
+
+    pub fn eta_duration(&self) -> Option<Duration> {
+        self.eta.map(Duration::from_secs)
+    }
+
+    pub fn seeding_time_duration(&self) -> Option<Duration> {
+        self.seeding_time.map(Duration::from_secs)
+    }
+
+    pub fn time_active_duration(&self) -> Duration {
+        Duration::from_secs(self.time_active)
+    }
+}
+
+// A torrent of sync/maindata: keyed by hash, and only the fields changed since the last rid are present.
+// Fields are None if they are missing, fields with sentinel values as in Torrent are Some(None) for the sentinel
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct PartialTorrent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub added_on: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_left: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_tmm: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "sentinel::non_negative_f64::partial")]
+    pub availability: Option<Option<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "sentinel::timestamp::partial")]
+    pub completion_on: Option<Option<Timestamp>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "sentinel::positive::partial")]
+    pub dl_limit: Option<Option<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dlspeed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downloaded: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downloaded_session: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "sentinel::eta::partial")]
+    pub eta: Option<Option<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub f_l_piece_prio: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force_start: Option<bool>,
+    #[serde(rename = "private", alias = "isPrivate", skip_serializing_if = "Option::is_none")]
+    pub is_private: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "sentinel::timestamp_or_zero::partial")]
+    pub last_activity: Option<Option<Timestamp>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub magnet_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_ratio: Option<ShareLimit<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_seeding_time: Option<ShareLimit<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_complete: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_incomplete: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_leechs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_seeds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "sentinel::positive::partial")]
+    pub priority: Option<Option<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ratio: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub save_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seeding_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "sentinel::timestamp::partial")]
+    pub seen_complete: Option<Option<Timestamp>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seq_dl: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub super_seeding: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_active: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracker: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "sentinel::positive::partial")]
+    pub up_limit: Option<Option<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uploaded: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uploaded_session: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upspeed: Option<u64>
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct TorrentListParams {
     pub filter: TorrentListFilter,
-    pub category: String,
-    pub tag: String,
-    pub sort: Option<String>,
+    pub category: CategoryFilter,
+    pub tag: TagFilter,
+    pub sort: Option<TorrentSortField>,
     pub reverse: bool,
     pub limit: Option<usize>,
     pub offset: Option<isize>,
@@ -340,8 +1004,8 @@ impl Default for TorrentListParams {
     fn default() -> Self {
         TorrentListParams {
             filter: TorrentListFilter::All,
-            category: String::new(),
-            tag: String::new(),
+            category: CategoryFilter::Any,
+            tag: TagFilter::Any,
             sort: None,
             reverse: false,
             limit: None,
@@ -351,7 +1015,54 @@ impl Default for TorrentListParams {
     }
 }
 
-#[derive(Debug, Clone)]
+// TorrentListParams::new().filter(TorrentListFilter::Seeding).sort_by(TorrentSortField::Ratio).reverse().page(100, 0)
+impl TorrentListParams {
+    pub fn new() -> Self {
+        TorrentListParams::default()
+    }
+
+    pub fn filter(mut self, filter: TorrentListFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    // a category name, or a CategoryFilter
+    pub fn category<C: Into<CategoryFilter>>(mut self, category: C) -> Self {
+        self.category = category.into();
+        self
+    }
+
+    // a tag, or a TagFilter
+    pub fn tag<T: Into<TagFilter>>(mut self, tag: T) -> Self {
+        self.tag = tag.into();
+        self
+    }
+
+    pub fn sort_by(mut self, field: TorrentSortField) -> Self {
+        self.sort = Some(field);
+        self
+    }
+
+    pub fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    // offset can be negative to count from the end of the list
+    pub fn page(mut self, limit: usize, offset: isize) -> Self {
+        self.limit = Some(limit);
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn hashes<S: ToString>(mut self, hashes: S) -> Self {
+        self.hashes = Some(hashes.to_string());
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TorrentListFilter {
     All,
     Downloading,
@@ -367,77 +1078,313 @@ pub enum TorrentListFilter {
     Errored
 }
 
-impl ToString for TorrentListFilter {
-    fn to_string(&self) -> String {
-        match *self {
-            TorrentListFilter::All => String::from("all"),
-            TorrentListFilter::Downloading => String::from("downloading"),
-            TorrentListFilter::Seeding => String::from("seeding"),
-            TorrentListFilter::Completed => String::from("completed"),
-            TorrentListFilter::Paused => String::from("paused"),
-            TorrentListFilter::Active => String::from("active"),
-            TorrentListFilter::Inactive => String::from("inactive"),
-            TorrentListFilter::Resumed => String::from("resumed"),
-            TorrentListFilter::Stalled => String::from("stalled"),
-            TorrentListFilter::StalledUploading => String::from("stalled_uploading"),
-            TorrentListFilter::StalledDownloading => String::from("stalled_downloading"),
-            TorrentListFilter::Errored => String::from("errored")
+impl fmt::Display for TorrentListFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            TorrentListFilter::All => "all",
+            TorrentListFilter::Downloading => "downloading",
+            TorrentListFilter::Seeding => "seeding",
+            TorrentListFilter::Completed => "completed",
+            TorrentListFilter::Paused => "paused",
+            TorrentListFilter::Active => "active",
+            TorrentListFilter::Inactive => "inactive",
+            TorrentListFilter::Resumed => "resumed",
+            TorrentListFilter::Stalled => "stalled",
+            TorrentListFilter::StalledUploading => "stalled_uploading",
+            TorrentListFilter::StalledDownloading => "stalled_downloading",
+            TorrentListFilter::Errored => "errored"
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub enum CategoryFilter {
+    #[default]
+    Any,
+    Uncategorized,
+    Named(String)
+}
+
+impl CategoryFilter {
+    // value of the category parameter, None to leave it out
+    pub fn as_param(&self) -> Option<&str> {
+        match self {
+            CategoryFilter::Any => None,
+            CategoryFilter::Uncategorized => Some(""),
+            CategoryFilter::Named(name) => Some(name)
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+// an empty name is qBittorrent's uncategorized filter
+impl From<&str> for CategoryFilter {
+    fn from(name: &str) -> Self {
+        CategoryFilter::from(name.to_string())
+    }
+}
+
+impl From<String> for CategoryFilter {
+    fn from(name: String) -> Self {
+        if name.is_empty() {
+            CategoryFilter::Uncategorized
+        } else {
+            CategoryFilter::Named(name)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub enum TagFilter {
+    #[default]
+    Any,
+    Untagged,
+    Named(String)
+}
+
+impl TagFilter {
+    // value of the tag parameter, None to leave it out
+    pub fn as_param(&self) -> Option<&str> {
+        match self {
+            TagFilter::Any => None,
+            TagFilter::Untagged => Some(""),
+            TagFilter::Named(tag) => Some(tag)
+        }
+    }
+}
+
+// an empty tag is qBittorrent's untagged filter
+impl From<&str> for TagFilter {
+    fn from(tag: &str) -> Self {
+        TagFilter::from(tag.to_string())
+    }
+}
+
+impl From<String> for TagFilter {
+    fn from(tag: String) -> Self {
+        if tag.is_empty() {
+            TagFilter::Untagged
+        } else {
+            TagFilter::Named(tag)
+        }
+    }
+}
+
+// Torrent properties torrents/info can sort by, named after the Torrent fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(into = "String")]
+pub enum TorrentSortField {
+    AddedOn,
+    AmountLeft,
+    AutoTmm,
+    Availability,
+    Category,
+    Completed,
+    CompletionOn,
+    ContentPath,
+    DlLimit,
+    DlSpeed,
+    Downloaded,
+    DownloadedSession,
+    Eta,
+    FirstLastPiecePriority,
+    ForceStart,
+    Hash,
+    IsPrivate,
+    LastActivity,
+    MagnetUri,
+    MaxRatio,
+    MaxSeedingTime,
+    Name,
+    NumComplete,
+    NumIncomplete,
+    NumLeechs,
+    NumSeeds,
+    Priority,
+    Progress,
+    Ratio,
+    SavePath,
+    SeedingTime,
+    SeenComplete,
+    SequentialDownload,
+    Size,
+    State,
+    SuperSeeding,
+    Tags,
+    TimeActive,
+    Tracker,
+    UpLimit,
+    Uploaded,
+    UploadedSession,
+    UpSpeed
+}
+
+impl fmt::Display for TorrentSortField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            TorrentSortField::AddedOn => "added_on",
+            TorrentSortField::AmountLeft => "amount_left",
+            TorrentSortField::AutoTmm => "auto_tmm",
+            TorrentSortField::Availability => "availability",
+            TorrentSortField::Category => "category",
+            TorrentSortField::Completed => "completed",
+            TorrentSortField::CompletionOn => "completion_on",
+            TorrentSortField::ContentPath => "content_path",
+            TorrentSortField::DlLimit => "dl_limit",
+            TorrentSortField::DlSpeed => "dlspeed",
+            TorrentSortField::Downloaded => "downloaded",
+            TorrentSortField::DownloadedSession => "downloaded_session",
+            TorrentSortField::Eta => "eta",
+            TorrentSortField::FirstLastPiecePriority => "f_l_piece_prio",
+            TorrentSortField::ForceStart => "force_start",
+            TorrentSortField::Hash => "hash",
+            TorrentSortField::IsPrivate => "private",
+            TorrentSortField::LastActivity => "last_activity",
+            TorrentSortField::MagnetUri => "magnet_uri",
+            TorrentSortField::MaxRatio => "max_ratio",
+            TorrentSortField::MaxSeedingTime => "max_seeding_time",
+            TorrentSortField::Name => "name",
+            TorrentSortField::NumComplete => "num_complete",
+            TorrentSortField::NumIncomplete => "num_incomplete",
+            TorrentSortField::NumLeechs => "num_leechs",
+            TorrentSortField::NumSeeds => "num_seeds",
+            TorrentSortField::Priority => "priority",
+            TorrentSortField::Progress => "progress",
+            TorrentSortField::Ratio => "ratio",
+            TorrentSortField::SavePath => "save_path",
+            TorrentSortField::SeedingTime => "seeding_time",
+            TorrentSortField::SeenComplete => "seen_complete",
+            TorrentSortField::SequentialDownload => "seq_dl",
+            TorrentSortField::Size => "size",
+            TorrentSortField::State => "state",
+            TorrentSortField::SuperSeeding => "super_seeding",
+            TorrentSortField::Tags => "tags",
+            TorrentSortField::TimeActive => "time_active",
+            TorrentSortField::Tracker => "tracker",
+            TorrentSortField::UpLimit => "up_limit",
+            TorrentSortField::Uploaded => "uploaded",
+            TorrentSortField::UploadedSession => "uploaded_session",
+            TorrentSortField::UpSpeed => "upspeed"
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+impl From<TorrentSortField> for String {
+    fn from(field: TorrentSortField) -> String {
+        field.to_string()
+    }
+}
+
+// Sizes, piece counts and dates are None while a magnet link is fetching metadata
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct TorrentGenericProperties {
     pub save_path: String,
-    pub creation_date: usize,
-    pub piece_size: usize,
+    #[serde(default, with = "sentinel::timestamp")]
+    pub creation_date: Option<Timestamp>,
+    #[serde(default, with = "sentinel::non_negative")]
+    pub piece_size: Option<u64>,
     pub comment: String,
-    pub total_wasted: usize,
-    pub total_uploaded: usize,
-    pub total_uploaded_session: usize,
-    pub total_downloaded: usize,
-    pub total_downloaded_session: usize,
-    pub up_limit: isize,
-    pub dl_limit: isize,
-    pub time_elapsed: usize,
-    pub seeding_time: usize,
-    pub nb_connections: usize,
-    pub nb_connections_limit: usize,
+    pub total_wasted: u64,
+    pub total_uploaded: u64,
+    pub total_uploaded_session: u64,
+    pub total_downloaded: u64,
+    pub total_downloaded_session: u64,
+    #[serde(default, with = "sentinel::positive")]
+    pub up_limit: Option<u64>,
+    #[serde(default, with = "sentinel::positive")]
+    pub dl_limit: Option<u64>,
+    pub time_elapsed: u64,
+    pub seeding_time: u64,
+    pub nb_connections: u64,
+    #[serde(default, with = "sentinel::positive")]
+    pub nb_connections_limit: Option<u64>,
     pub share_ratio: f64,
-    pub addition_date: usize,
-    pub completion_date: usize,
+    pub addition_date: Timestamp,
+    #[serde(default, with = "sentinel::timestamp")]
+    pub completion_date: Option<Timestamp>,
     pub created_by: String,
-    pub dl_speed_avg: usize,
-    pub dl_speed: usize,
-    pub eta: usize,
-    pub last_seen: usize,
-    pub peers: usize,
-    pub peers_total: usize,
-    pub pieces_have: usize,
-    pub pieces_num: usize,
-    pub reannounce: usize,
-    pub seeds: usize,
-    pub seeds_total: usize,
-    pub total_size: usize,
-    pub up_speed_avg: usize,
-    pub up_speed: usize,
-    #[serde(rename = "isPrivate")]
-    pub is_private: bool
-}
-
-#[derive(Debug, Clone, Deserialize)]
+    pub dl_speed_avg: u64,
+    pub dl_speed: u64,
+    #[serde(default, with = "sentinel::eta")]
+    pub eta: Option<u64>,
+    #[serde(default, with = "sentinel::timestamp")]
+    pub last_seen: Option<Timestamp>,
+    pub peers: u64,
+    pub peers_total: u64,
+    #[serde(default, with = "sentinel::non_negative")]
+    pub pieces_have: Option<u64>,
+    #[serde(default, with = "sentinel::non_negative")]
+    pub pieces_num: Option<u64>,
+    // seconds until the next announce
+    #[serde(default, with = "sentinel::non_negative")]
+    pub reannounce: Option<u64>,
+    pub seeds: u64,
+    pub seeds_total: u64,
+    #[serde(default, with = "sentinel::non_negative")]
+    pub total_size: Option<u64>,
+    pub up_speed_avg: u64,
+    pub up_speed: u64,
+    // reported by qBittorrent 4.6 and later
+    #[serde(rename = "is_private", alias = "isPrivate")]
+    pub is_private: Option<bool>
+}
+
+impl TorrentGenericProperties {
+    pub fn creation_date_time(&self) -> Option<SystemTime> {
+        self.creation_date.map(unix_time)
+    }
+
+    pub fn addition_date_time(&self) -> SystemTime {
+        unix_time(self.addition_date)
+    }
+
+    pub fn completion_date_time(&self) -> Option<SystemTime> {
+        self.completion_date.map(unix_time)
+    }
+
+    pub fn last_seen_time(&self) -> Option<SystemTime> {
+        self.last_seen.map(unix_time)
+    }
+
+    pub fn eta_duration(&self) -> Option<Duration> {
+        self.eta.map(Duration::from_secs)
+    }
+
+    pub fn seeding_time_duration(&self) -> Duration {
+        Duration::from_secs(self.seeding_time)
+    }
+
+    pub fn time_elapsed_duration(&self) -> Duration {
+        Duration::from_secs(self.time_elapsed)
+    }
+
+    pub fn reannounce_duration(&self) -> Option<Duration> {
+        self.reannounce.map(Duration::from_secs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct TorrentTracker {
     pub url: String,
-    pub status: usize,
-    pub tier: isize,
-    pub num_peers: usize,
-    pub num_seeds: usize,
-    pub num_leeches: usize,
-    pub num_downloaded: usize,
+    pub status: u8,
+    // -1 for the DHT, PeX and LSD entries
+    pub tier: i64,
+    // None if the tracker has not been contacted yet
+    #[serde(default, with = "sentinel::non_negative")]
+    pub num_peers: Option<u64>,
+    #[serde(default, with = "sentinel::non_negative")]
+    pub num_seeds: Option<u64>,
+    #[serde(default, with = "sentinel::non_negative")]
+    pub num_leeches: Option<u64>,
+    #[serde(default, with = "sentinel::non_negative")]
+    pub num_downloaded: Option<u64>,
     pub msg: String
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct TorrentWebSeed {
     pub url: String
 }
@@ -0,0 +1,212 @@
+use std::{
+    fs,
+    path::Path
+};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    models::{AppPreferences, JsonObject},
+    QBittorrentError
+};
+
+// Removed from exports when redact is set
+pub const SECRET_PREFERENCES: [&str; 4] = [
+    "web_ui_password",
+    "proxy_password",
+    "mail_notification_password",
+    "dyndns_password"
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreferenceChange {
+    pub key: String,
+    // None if the server does not report the key (e.g. web_ui_password is never returned)
+    pub current: Option<Value>,
+    pub desired: Value
+}
+
+impl AppPreferences {
+    // Only the set fields (including extra), keyed by their WebAPI names
+    pub fn to_json_map(&self) -> Map<String, Value> {
+        let mut map = match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new()
+        };
+
+        map.retain(|_, v| !v.is_null());
+        map
+    }
+
+    pub fn redacted(&self) -> AppPreferences {
+        let mut prefs = self.clone();
+        prefs.web_ui_password = None;
+        prefs.proxy_password = None;
+        prefs.mail_notification_password = None;
+        prefs.dyndns_password = None;
+        prefs.extra.retain(|key, _| !SECRET_PREFERENCES.contains(&key.as_str()));
+        prefs
+    }
+
+    pub fn to_json(&self, redact: bool) -> Result<String, QBittorrentError> {
+        let prefs = if redact { self.redacted() } else { self.clone() };
+        Ok(serde_json::to_string_pretty(&prefs.to_json_map())?)
+    }
+
+    pub fn from_json(json: &str) -> Result<AppPreferences, QBittorrentError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn to_toml(&self, redact: bool) -> Result<String, QBittorrentError> {
+        let prefs = if redact { self.redacted() } else { self.clone() };
+        Ok(toml::to_string_pretty(&prefs.to_json_map())?)
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<AppPreferences, QBittorrentError> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    // The format is picked by the file extension: .toml (with the toml feature) or JSON otherwise
+    pub fn export<P: AsRef<Path>>(&self, path: P, redact: bool) -> Result<(), QBittorrentError> {
+        let contents = if is_toml(path.as_ref()) {
+            #[cfg(feature = "toml")]
+            { self.to_toml(redact)? }
+            #[cfg(not(feature = "toml"))]
+            { return Err(toml_disabled()); }
+        } else {
+            self.to_json(redact)?
+        };
+
+        Ok(fs::write(path, contents)?)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<AppPreferences, QBittorrentError> {
+        let contents = fs::read_to_string(path.as_ref())?;
+
+        if is_toml(path.as_ref()) {
+            #[cfg(feature = "toml")]
+            { AppPreferences::from_toml(&contents) }
+            #[cfg(not(feature = "toml"))]
+            { Err(toml_disabled()) }
+        } else {
+            AppPreferences::from_json(&contents)
+        }
+    }
+
+    // Keys set in self (the desired state) whose value differs from live; keys unset in self are left alone
+    pub fn diff(&self, live: &AppPreferences) -> Vec<PreferenceChange> {
+        let live = live.to_json_map();
+
+        self.to_json_map().into_iter()
+            .filter(|(key, desired)| live.get(key) != Some(desired))
+            .map(|(key, desired)| PreferenceChange {
+                current: live.get(&key).cloned(),
+                key,
+                desired
+            })
+            .collect()
+    }
+}
+
+impl FromIterator<PreferenceChange> for JsonObject {
+    fn from_iter<I: IntoIterator<Item = PreferenceChange>>(changes: I) -> Self {
+        let map = changes.into_iter()
+            .map(|change| (change.key, change.desired))
+            .collect::<Map<_, _>>();
+
+        JsonObject(Value::Object(map))
+    }
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+}
+
+#[cfg(not(feature = "toml"))]
+fn toml_disabled() -> QBittorrentError {
+    QBittorrentError::BadInput("toml support requires the toml feature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::PathBuf, process};
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn preferences() -> AppPreferences {
+        let mut prefs = AppPreferences::from_json(&json!({
+            "save_path": "/downloads",
+            "max_ratio": -1,
+            "web_ui_port": 8080,
+            "web_ui_password": "secret",
+            "proxy_type": "HTTP",
+            "proxy_password": "secret",
+            "mail_notification_password": "secret",
+            "scan_dirs": { "/watch": 1 },
+            "some_future_preference": [1, 2]
+        }).to_string()).unwrap();
+
+        // a secret whose type changed ends up in extra
+        prefs.extra.insert("dyndns_password".to_string(), json!(1234));
+        prefs
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("qbittorrent-client-{}-{}", process::id(), name))
+    }
+
+    fn assert_redacted(map: &Map<String, Value>) {
+        for key in SECRET_PREFERENCES {
+            assert!(!map.contains_key(key), "{} was exported", key);
+        }
+
+        assert_eq!(map["save_path"], "/downloads");
+        assert_eq!(map["some_future_preference"], json!([1, 2]));
+    }
+
+    #[test]
+    fn redacts_secrets() {
+        let prefs = preferences();
+        assert!(SECRET_PREFERENCES.iter().all(|key| prefs.to_json_map().contains_key(*key)));
+
+        assert_redacted(&serde_json::from_str(&prefs.to_json(true).unwrap()).unwrap());
+
+        let path = temp_path("redacted.json");
+        prefs.export(&path, true).unwrap();
+        let exported = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_redacted(&serde_json::from_str(&exported).unwrap());
+    }
+
+    #[test]
+    fn round_trips_json() {
+        let prefs = preferences();
+        let path = temp_path("round-trip.json");
+        prefs.export(&path, false).unwrap();
+        let loaded = AppPreferences::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.to_json_map(), prefs.to_json_map());
+        assert!(loaded.diff(&prefs).is_empty());
+        assert!(prefs.diff(&loaded).is_empty());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn round_trips_toml() {
+        let prefs = preferences();
+        let path = temp_path("round-trip.toml");
+        prefs.export(&path, false).unwrap();
+        let loaded = AppPreferences::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(loaded.diff(&prefs).is_empty(), "{:?}", loaded.diff(&prefs));
+        assert!(prefs.diff(&loaded).is_empty());
+
+        assert_redacted(&AppPreferences::from_toml(&prefs.to_toml(true).unwrap()).unwrap().to_json_map());
+    }
+}
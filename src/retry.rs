@@ -0,0 +1,72 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration
+};
+
+use crate::QBittorrentError;
+
+// Retries requests failed because of connection errors (including connections dropped mid-request or mid-body),
+// timeouts or 5xx responses. Each attempt is limited by timeout, None waits forever.
+// Only GET requests are retried, unless retry_non_idempotent is set (e.g. torrent_delete or shutdown would be repeated too).
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub jitter: bool,
+    pub retry_non_idempotent: bool,
+    pub timeout: Option<Duration>
+}
+
+impl RetryPolicy {
+    pub fn disabled() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn should_retry(&self, attempt: u32, err: &QBittorrentError) -> bool {
+        if attempt >= self.max_retries {
+            return false;
+        }
+
+        match *err {
+            QBittorrentError::Reqwest(ref err) => {
+                // reqwest reports a body cut short while reading it as a decode error
+                err.is_connect() || err.is_timeout() || err.is_request() || err.is_body() || err.is_decode()
+            }
+            QBittorrentError::Status { code, .. } => code >= 500,
+            _ => false
+        }
+    }
+
+    // initial_backoff * 2^attempt capped at max_backoff; with jitter a random delay between the half and the full backoff
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff);
+
+        if !self.jitter {
+            return backoff;
+        }
+
+        let half = backoff / 2;
+        let random = RandomState::new().build_hasher().finish();
+        half + half.mul_f64(random as f64 / u64::MAX as f64)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            jitter: true,
+            retry_non_idempotent: false,
+            timeout: Some(Duration::from_secs(30))
+        }
+    }
+}
@@ -0,0 +1,213 @@
+#[cfg(feature = "chrono")]
+use chrono::{Datelike, Local, Timelike, Weekday};
+
+use crate::{
+    models::{AppPreferences, AppPreferencesPatch, SchedulerDays},
+    QBittorrentError
+};
+
+// qBittorrent's alternative speed limits scheduler (scheduler_enabled, schedule_from_hour/min, schedule_to_hour/min, scheduler_days).
+// The fields are public, so a schedule built directly is checked again by to_preferences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeedSchedule {
+    pub enabled: bool,
+    pub from: TimeOfDay,
+    pub to: TimeOfDay,
+    pub days: SchedulerDays
+}
+
+// Hour and minute of a schedule, ordered by hour first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimeOfDay {
+    pub hour: u32,
+    pub minute: u32
+}
+
+impl TimeOfDay {
+    pub fn new(hour: u32, minute: u32) -> Result<Self, QBittorrentError> {
+        if hour > 23 || minute > 59 {
+            return Err(QBittorrentError::BadInput(format!("invalid schedule time: {:02}:{:02}", hour, minute)));
+        }
+
+        Ok(TimeOfDay { hour, minute })
+    }
+}
+
+impl SpeedSchedule {
+    pub fn new(enabled: bool, from: (u32, u32), to: (u32, u32), days: SchedulerDays) -> Result<Self, QBittorrentError> {
+        let schedule = SpeedSchedule {
+            enabled,
+            from: TimeOfDay { hour: from.0, minute: from.1 },
+            to: TimeOfDay { hour: to.0, minute: to.1 },
+            days
+        };
+
+        schedule.validate()?;
+        Ok(schedule)
+    }
+
+    pub fn validate(&self) -> Result<(), QBittorrentError> {
+        if let SchedulerDays::Other(code) = self.days {
+            return Err(QBittorrentError::BadInput(format!("invalid scheduler days: {}", code)));
+        }
+
+        TimeOfDay::new(self.from.hour, self.from.minute)?;
+        TimeOfDay::new(self.to.hour, self.to.minute)?;
+
+        Ok(())
+    }
+
+    pub fn from_preferences(prefs: &AppPreferences) -> Result<Self, QBittorrentError> {
+        let field = |value: Option<u64>, name: &str| -> Result<u32, QBittorrentError> {
+            value
+                .and_then(|value| u32::try_from(value).ok())
+                .ok_or_else(|| QBittorrentError::BadInput(format!("{} is missing from preferences", name)))
+        };
+
+        SpeedSchedule::new(
+            prefs.scheduler_enabled.unwrap_or(false),
+            (field(prefs.schedule_from_hour, "schedule_from_hour")?, field(prefs.schedule_from_min, "schedule_from_min")?),
+            (field(prefs.schedule_to_hour, "schedule_to_hour")?, field(prefs.schedule_to_min, "schedule_to_min")?),
+            prefs.scheduler_days.ok_or_else(|| QBittorrentError::BadInput("scheduler_days is missing from preferences".to_string()))?
+        )
+    }
+
+    pub fn to_preferences(&self) -> Result<AppPreferencesPatch, QBittorrentError> {
+        self.validate()?;
+
+        Ok(AppPreferences {
+            scheduler_enabled: Some(self.enabled),
+            schedule_from_hour: Some(self.from.hour as u64),
+            schedule_from_min: Some(self.from.minute as u64),
+            schedule_to_hour: Some(self.to.hour as u64),
+            schedule_to_min: Some(self.to.minute as u64),
+            scheduler_days: Some(self.days),
+            ..Default::default()
+        })
+    }
+
+    // Requires the chrono feature.
+    // Mirrors qBittorrent's BandwidthScheduler: both ends are inclusive, and a window crossing midnight (from > to)
+    // is inverted, so it is active for the whole day on days which don't match `days`
+    #[cfg(feature = "chrono")]
+    pub fn is_active_at<T: Datelike + Timelike>(&self, time: &T) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        let (start, end, mut active) = if self.from > self.to {
+            (self.to, self.from, true)
+        } else {
+            (self.from, self.to, false)
+        };
+
+        let now = (time.hour(), time.minute(), time.second());
+
+        if (start.hour, start.minute, 0) <= now && now <= (end.hour, end.minute, 0) && self.matches_day(time.weekday()) {
+            active = !active;
+        }
+
+        active
+    }
+
+    #[cfg(feature = "chrono")]
+    pub fn is_active_now(&self) -> bool {
+        self.is_active_at(&Local::now())
+    }
+
+    #[cfg(feature = "chrono")]
+    fn matches_day(&self, weekday: Weekday) -> bool {
+        let weekend = matches!(weekday, Weekday::Sat | Weekday::Sun);
+
+        match self.days {
+            SchedulerDays::Every => true,
+            SchedulerDays::Weekdays => !weekend,
+            SchedulerDays::Weekends => weekend,
+            SchedulerDays::Monday => weekday == Weekday::Mon,
+            SchedulerDays::Tuesday => weekday == Weekday::Tue,
+            SchedulerDays::Wednesday => weekday == Weekday::Wed,
+            SchedulerDays::Thursday => weekday == Weekday::Thu,
+            SchedulerDays::Friday => weekday == Weekday::Fri,
+            SchedulerDays::Saturday => weekday == Weekday::Sat,
+            SchedulerDays::Sunday => weekday == Weekday::Sun,
+            SchedulerDays::Other(_) => false
+        }
+    }
+}
+
+#[cfg(all(test, feature = "chrono"))]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::*;
+
+    // 2024-06-03 is a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 6, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn same_day_window() {
+        let schedule = SpeedSchedule::new(true, (8, 0), (20, 0), SchedulerDays::Every).unwrap();
+
+        assert!(!schedule.is_active_at(&at(3, 7, 59)));
+        assert!(schedule.is_active_at(&at(3, 8, 0)));
+        assert!(schedule.is_active_at(&at(3, 20, 0)));
+        assert!(!schedule.is_active_at(&at(3, 20, 1)));
+    }
+
+    #[test]
+    fn window_crossing_midnight() {
+        let schedule = SpeedSchedule::new(true, (22, 0), (6, 0), SchedulerDays::Every).unwrap();
+
+        assert!(schedule.is_active_at(&at(3, 23, 0)));
+        assert!(schedule.is_active_at(&at(4, 2, 0)));
+        assert!(!schedule.is_active_at(&at(3, 12, 0)));
+
+        // inverted on days which don't match, so active for the whole day
+        let schedule = SpeedSchedule::new(true, (22, 0), (6, 0), SchedulerDays::Monday).unwrap();
+
+        assert!(!schedule.is_active_at(&at(3, 12, 0)));
+        assert!(schedule.is_active_at(&at(4, 12, 0)));
+    }
+
+    #[test]
+    fn weekdays_and_weekends() {
+        let weekdays = SpeedSchedule::new(true, (8, 0), (20, 0), SchedulerDays::Weekdays).unwrap();
+        let weekends = SpeedSchedule::new(true, (8, 0), (20, 0), SchedulerDays::Weekends).unwrap();
+
+        // Friday, Saturday, Sunday
+        assert!(weekdays.is_active_at(&at(7, 12, 0)));
+        assert!(!weekdays.is_active_at(&at(8, 12, 0)));
+        assert!(!weekdays.is_active_at(&at(9, 12, 0)));
+
+        assert!(!weekends.is_active_at(&at(7, 12, 0)));
+        assert!(weekends.is_active_at(&at(8, 12, 0)));
+        assert!(weekends.is_active_at(&at(9, 12, 0)));
+    }
+
+    #[test]
+    fn disabled() {
+        let schedule = SpeedSchedule::new(false, (0, 0), (23, 59), SchedulerDays::Every).unwrap();
+        assert!(!schedule.is_active_at(&at(3, 12, 0)));
+
+        let schedule = SpeedSchedule::new(false, (22, 0), (6, 0), SchedulerDays::Monday).unwrap();
+        assert!(!schedule.is_active_at(&at(4, 12, 0)));
+    }
+
+    #[test]
+    fn rejects_invalid_times() {
+        assert!(SpeedSchedule::new(true, (24, 0), (6, 0), SchedulerDays::Every).is_err());
+        assert!(SpeedSchedule::new(true, (22, 0), (6, 60), SchedulerDays::Every).is_err());
+
+        let mut schedule = SpeedSchedule::new(true, (22, 0), (6, 0), SchedulerDays::Every).unwrap();
+        assert_eq!(schedule.to_preferences().unwrap().schedule_from_hour, Some(22));
+
+        schedule.to.hour = 24;
+        assert!(matches!(schedule.to_preferences(), Err(QBittorrentError::BadInput(_))));
+
+        schedule.to = TimeOfDay { hour: 6, minute: 0 };
+        schedule.days = SchedulerDays::Other(42);
+        assert!(matches!(schedule.to_preferences(), Err(QBittorrentError::BadInput(_))));
+    }
+}
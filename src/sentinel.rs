@@ -0,0 +1,160 @@
+// serde(with) modules reading qBittorrent's "unknown" / "not applicable" values as None.
+// Serialization is normalized rather than lossless: None is written back as the value qBittorrent 5.x uses,
+// e.g. a 4294967295 timestamp of older versions as -1 and a -1 limit or queue position as 0
+
+use serde::{Deserialize, Deserializer, Serializer};
+
+// <module>::partial reads Option<Option<T>> for objects of sync/maindata, where a missing key
+// (with serde(default)) is None and a present sentinel Some(None)
+macro_rules! partial {
+    ($ty:ty) => {
+        pub(crate) mod partial {
+            use super::*;
+
+            pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Option<Option<$ty>>, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                super::deserialize(deserializer).map(Some)
+            }
+
+            pub(crate) fn serialize<S>(value: &Option<Option<$ty>>, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                match value {
+                    Some(value) => super::serialize(value, serializer),
+                    None => serializer.serialize_none()
+                }
+            }
+        }
+    };
+}
+
+// qBittorrent reports an ETA of 100 days (8640000 seconds) for torrents which won't finish
+pub(crate) const MAX_ETA: u64 = 8640000;
+
+// -1 if not set; qBittorrent 4.1 sent 4294967295 (uint32 -1) and some fields 0 instead
+pub(crate) mod timestamp {
+    use super::*;
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Option::<i64>::deserialize(deserializer)?;
+        Ok(value.filter(|&timestamp| timestamp > 0 && timestamp != u32::MAX as i64))
+    }
+
+    pub(crate) fn serialize<S>(value: &Option<i64>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_i64(value.unwrap_or(-1))
+    }
+
+    partial!(i64);
+}
+
+// timestamps which are 0 if not set, e.g. last_activity of a torrent which was never active
+pub(crate) mod timestamp_or_zero {
+    use super::*;
+
+    pub(crate) use super::timestamp::deserialize;
+
+    pub(crate) fn serialize<S>(value: &Option<i64>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_i64(value.unwrap_or(0))
+    }
+
+    partial!(i64);
+}
+
+// -1 if unknown, e.g. sizes of a torrent without metadata
+pub(crate) mod non_negative {
+    use super::*;
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Option::<i64>::deserialize(deserializer)?;
+        Ok(value.and_then(|value| u64::try_from(value).ok()))
+    }
+
+    pub(crate) fn serialize<S>(value: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *value {
+            Some(value) => serializer.serialize_u64(value),
+            None => serializer.serialize_i64(-1)
+        }
+    }
+}
+
+pub(crate) mod non_negative_f64 {
+    use super::*;
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Option::<f64>::deserialize(deserializer)?;
+        Ok(value.filter(|&value| value >= 0.0))
+    }
+
+    pub(crate) fn serialize<S>(value: &Option<f64>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_f64(value.unwrap_or(-1.0))
+    }
+
+    partial!(f64);
+}
+
+// 0 or -1 for no limit / not queued
+pub(crate) mod positive {
+    use super::*;
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Option::<i64>::deserialize(deserializer)?;
+        Ok(value.and_then(|value| u64::try_from(value).ok()).filter(|&value| value > 0))
+    }
+
+    pub(crate) fn serialize<S>(value: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u64(value.unwrap_or(0))
+    }
+
+    partial!(u64);
+}
+
+pub(crate) mod eta {
+    use super::*;
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Option::<i64>::deserialize(deserializer)?;
+        Ok(value.and_then(|value| u64::try_from(value).ok()).filter(|&eta| eta < MAX_ETA))
+    }
+
+    pub(crate) fn serialize<S>(value: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u64(value.unwrap_or(MAX_ETA))
+    }
+
+    partial!(u64);
+}
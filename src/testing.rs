@@ -0,0 +1,1002 @@
+// In-process fake of the qBittorrent WebAPI with in-memory state, for hermetic tests against QBittorrentClient.
+//
+// let server = MockServer::start().await?;
+// server.add_torrent(mock_torrent("8c212779b4abde7c6bc608063a0d008b7e40ce32", "debian.iso"));
+// let client = QBittorrentClient::new(server.url()).await?;
+// client.login(MOCK_USERNAME, MOCK_PASSWORD).await?;
+//
+// Implemented endpoints: auth, app (version, preferences), log, sync/maindata, transfer and
+// torrents (info, properties, trackers, webseeds, pause/resume or stop/start, delete).
+
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::RandomState, BTreeMap, HashMap, HashSet, VecDeque},
+    hash::{BuildHasher, Hasher},
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, SystemTime, UNIX_EPOCH}
+};
+
+use serde_json::{json, Map, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle
+};
+
+use crate::{
+    models::LogLevel,
+    version::ApiVersion
+};
+
+pub const MOCK_USERNAME: &str = "admin";
+pub const MOCK_PASSWORD: &str = "adminadmin";
+
+// failed logins before the IP is banned, like web_ui_max_auth_fail_count
+const MAX_AUTH_FAIL_COUNT: usize = 5;
+// sync/maindata snapshots kept for computing deltas
+const MAX_SNAPSHOTS: usize = 16;
+
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    task: JoinHandle<()>
+}
+
+impl MockServer {
+    pub async fn start() -> io::Result<MockServer> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        let task_state = state.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve_connection(stream, task_state.clone()));
+            }
+        });
+
+        Ok(MockServer { addr, state, task })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    pub fn set_credentials(&self, username: &str, password: &str) {
+        let mut state = self.state();
+        state.username = username.to_string();
+        state.password = password.to_string();
+    }
+
+    // e.g. ("v5.0.2", "2.11.2") switches torrents/pause and torrents/resume to torrents/stop and torrents/start
+    pub fn set_versions(&self, app_version: &str, api_version: &str) {
+        let mut state = self.state();
+        state.app_version = app_version.to_string();
+        state.api_version = api_version.to_string();
+    }
+
+    // Invalidates all sessions, as qBittorrent does after session timeout or restart
+    pub fn expire_sessions(&self) {
+        self.state().sessions.clear();
+    }
+
+    // The next `count` requests to `path` (e.g. "/api/v2/torrents/info") are answered with `status`
+    pub fn fail_next(&self, path: &str, status: u16, count: usize) {
+        self.state().failures.insert(path.to_string(), (Fault::Status(status), count));
+    }
+
+    // The next `count` requests to `path` are handled, but the connection is closed without a response
+    pub fn drop_next(&self, path: &str, count: usize) {
+        self.state().failures.insert(path.to_string(), (Fault::Drop, count));
+    }
+
+    // The next `count` responses to `path` are cut off halfway through the body
+    pub fn truncate_next(&self, path: &str, count: usize) {
+        self.state().failures.insert(path.to_string(), (Fault::Truncate, count));
+    }
+
+    // The next `count` responses to `path` are sent only after `delay`
+    pub fn delay_next(&self, path: &str, delay: Duration, count: usize) {
+        self.state().failures.insert(path.to_string(), (Fault::Delay(delay), count));
+    }
+
+    // Like a reverse proxy in front of qBittorrent: requests without this header (e.g. "Authorization")
+    // and value are answered with 401 before reaching the WebAPI
+    pub fn require_header(&self, name: &str, value: &str) {
+        self.state().required_headers.insert(name.to_ascii_lowercase(), value.to_string());
+    }
+
+    // Number of requests received for `path`, including failed ones
+    pub fn request_count(&self, path: &str) -> usize {
+        self.state().requests.get(path).copied().unwrap_or(0)
+    }
+
+    // torrent must be a JSON object with at least a "hash" key, see mock_torrent
+    pub fn add_torrent(&self, torrent: Value) {
+        let hash = torrent["hash"].as_str().expect("torrent without hash").to_string();
+        self.state().torrents.insert(hash, torrent);
+    }
+
+    // Merges the fields into the torrent, returns false if there is no such torrent
+    pub fn update_torrent(&self, hash: &str, fields: Value) -> bool {
+        let mut state = self.state();
+
+        match (state.torrents.get_mut(hash), fields) {
+            (Some(Value::Object(torrent)), Value::Object(fields)) => {
+                torrent.extend(fields);
+                true
+            }
+            _ => false
+        }
+    }
+
+    pub fn remove_torrent(&self, hash: &str) -> Option<Value> {
+        self.state().torrents.remove(hash)
+    }
+
+    pub fn torrent(&self, hash: &str) -> Option<Value> {
+        self.state().torrents.get(hash).cloned()
+    }
+
+    pub fn add_category(&self, name: &str, save_path: &str) {
+        self.state().categories.insert(name.to_string(), json!({ "name": name, "savePath": save_path }));
+    }
+
+    pub fn add_tag(&self, tag: &str) {
+        self.state().tags.insert(tag.to_string());
+    }
+
+    pub fn push_log(&self, level: LogLevel, message: &str) {
+        let mut state = self.state();
+        let id = state.logs.len();
+
+        state.logs.push(json!({
+            "id": id,
+            "message": message,
+            "timestamp": unix_time(),
+            "type": u8::from(level)
+        }));
+    }
+
+    pub fn push_peer_log(&self, ip: &str, blocked: bool, reason: &str) {
+        let mut state = self.state();
+        let id = state.peer_logs.len();
+
+        state.peer_logs.push(json!({
+            "id": id,
+            "ip": ip,
+            "timestamp": unix_time(),
+            "blocked": blocked,
+            "reason": reason
+        }));
+    }
+
+    pub fn preferences(&self) -> Map<String, Value> {
+        self.state().preferences.clone()
+    }
+
+    pub fn banned_peers(&self) -> Vec<String> {
+        self.state().banned_peers.clone()
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// A seeding torrent with every field of /api/v2/torrents/info, adjust it with serde_json before adding it
+pub fn mock_torrent(hash: &str, name: &str) -> Value {
+    let added_on = unix_time() - 86400;
+
+    // split in two, a single json! of this size hits the macro recursion limit
+    let mut torrent = json!({
+        "added_on": added_on,
+        "amount_left": 0,
+        "auto_tmm": false,
+        "availability": -1,
+        "category": "",
+        "completed": 1073741824,
+        "completion_on": added_on + 3600,
+        "content_path": format!("/downloads/{}", name),
+        "dl_limit": -1,
+        "dlspeed": 0,
+        "downloaded": 1073741824,
+        "downloaded_session": 0,
+        "eta": 8640000,
+        "f_l_piece_prio": false,
+        "force_start": false,
+        "hash": hash,
+        "last_activity": added_on + 3600,
+        "magnet_uri": format!("magnet:?xt=urn:btih:{}&dn={}", hash, name)
+    });
+
+    let rest = json!({
+        "max_ratio": -1,
+        "max_seeding_time": 0,
+        "name": name,
+        "num_complete": 10,
+        "num_incomplete": 2,
+        "num_leechs": 0,
+        "num_seeds": 0,
+        "priority": 0,
+        "progress": 1,
+        "ratio": 0.5,
+        "save_path": "/downloads",
+        "seeding_time": 82800,
+        "seen_complete": added_on + 3600,
+        "seq_dl": false,
+        "size": 1073741824,
+        "state": "stalledUP",
+        "super_seeding": false,
+        "tags": "",
+        "time_active": 86400,
+        "tracker": "http://tracker.example.org/announce",
+        "up_limit": -1,
+        "uploaded": 536870912,
+        "uploaded_session": 0,
+        "upspeed": 0
+    });
+
+    if let (Value::Object(torrent), Value::Object(rest)) = (&mut torrent, rest) {
+        torrent.extend(rest);
+    }
+
+    torrent
+}
+
+struct Snapshot {
+    torrents: BTreeMap<String, Value>,
+    categories: BTreeMap<String, Value>,
+    tags: HashSet<String>,
+    server_state: Value
+}
+
+struct MockState {
+    username: String,
+    password: String,
+    app_version: String,
+    api_version: String,
+    sessions: HashSet<String>,
+    auth_failures: usize,
+    requests: HashMap<String, usize>,
+    required_headers: HashMap<String, String>,
+    failures: HashMap<String, (Fault, usize)>,
+    preferences: Map<String, Value>,
+    torrents: BTreeMap<String, Value>,
+    categories: BTreeMap<String, Value>,
+    tags: HashSet<String>,
+    logs: Vec<Value>,
+    peer_logs: Vec<Value>,
+    banned_peers: Vec<String>,
+    alt_speed_enabled: bool,
+    dl_limit: u64,
+    up_limit: u64,
+    alt_dl_limit: u64,
+    alt_up_limit: u64,
+    rid: u64,
+    snapshots: VecDeque<(u64, Snapshot)>
+}
+
+impl Default for MockState {
+    fn default() -> Self {
+        let preferences = json!({
+            "locale": "en",
+            "save_path": "/downloads",
+            "temp_path_enabled": false,
+            "temp_path": "/downloads/temp",
+            "queueing_enabled": true,
+            "max_active_downloads": 3,
+            "max_active_torrents": 5,
+            "max_active_uploads": 3,
+            "max_ratio_enabled": false,
+            "max_ratio": -1,
+            "max_ratio_act": 0,
+            "listen_port": 6881,
+            "dl_limit": 0,
+            "up_limit": 0,
+            "alt_dl_limit": 10240,
+            "alt_up_limit": 10240,
+            "scheduler_enabled": false,
+            "schedule_from_hour": 8,
+            "schedule_from_min": 0,
+            "schedule_to_hour": 20,
+            "schedule_to_min": 0,
+            "scheduler_days": 0,
+            "dht": true,
+            "pex": true,
+            "lsd": true,
+            "encryption": 0,
+            "proxy_type": "None",
+            "web_ui_port": 8080,
+            "web_ui_username": MOCK_USERNAME
+        });
+
+        MockState {
+            username: MOCK_USERNAME.to_string(),
+            password: MOCK_PASSWORD.to_string(),
+            app_version: "v4.6.5".to_string(),
+            api_version: "2.9.3".to_string(),
+            sessions: HashSet::new(),
+            auth_failures: 0,
+            requests: HashMap::new(),
+            required_headers: HashMap::new(),
+            failures: HashMap::new(),
+            preferences: match preferences {
+                Value::Object(map) => map,
+                _ => Map::new()
+            },
+            torrents: BTreeMap::new(),
+            categories: BTreeMap::new(),
+            tags: HashSet::new(),
+            logs: Vec::new(),
+            peer_logs: Vec::new(),
+            banned_peers: Vec::new(),
+            alt_speed_enabled: false,
+            dl_limit: 0,
+            up_limit: 0,
+            alt_dl_limit: 10240,
+            alt_up_limit: 10240,
+            rid: 0,
+            snapshots: VecDeque::new()
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Fault {
+    Status(u16),
+    Drop,
+    Truncate,
+    Delay(Duration)
+}
+
+struct Request {
+    method: String,
+    path: String,
+    params: HashMap<String, String>,
+    // keyed by lowercase name
+    headers: HashMap<String, String>,
+    cookies: HashMap<String, String>
+}
+
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+    set_cookie: Option<String>,
+    fault: Option<Fault>
+}
+
+impl Response {
+    fn text<S: ToString>(body: S) -> Self {
+        Response {
+            status: 200,
+            content_type: "text/plain; charset=UTF-8",
+            body: body.to_string(),
+            set_cookie: None,
+            fault: None
+        }
+    }
+
+    fn json(body: Value) -> Self {
+        Response {
+            status: 200,
+            content_type: "application/json",
+            body: body.to_string(),
+            set_cookie: None,
+            fault: None
+        }
+    }
+
+    fn status(status: u16, body: &str) -> Self {
+        Response {
+            status,
+            ..Response::text(body)
+        }
+    }
+
+    fn ok() -> Self {
+        Response::text("")
+    }
+}
+
+async fn serve_connection(stream: TcpStream, state: Arc<Mutex<MockState>>) {
+    let mut stream = BufReader::new(stream);
+
+    let response = match read_request(&mut stream).await {
+        Ok(Some(req)) => {
+            let mut state = state.lock().unwrap_or_else(|err| err.into_inner());
+            state.handle(&req)
+        }
+        Ok(None) => return,
+        Err(_) => Response::status(400, "Bad Request")
+    };
+
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        409 => "Conflict",
+        415 => "Unsupported Media Type",
+        _ => "Error"
+    };
+
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status, reason, response.content_type, response.body.len()
+    );
+
+    if let Some(cookie) = response.set_cookie {
+        head.push_str(&format!("Set-Cookie: {}\r\n", cookie));
+    }
+
+    head.push_str("\r\n");
+
+    let body = match response.fault {
+        Some(Fault::Drop) => return,
+        Some(Fault::Truncate) => &response.body[..response.body.len() / 2],
+        Some(Fault::Delay(delay)) => {
+            tokio::time::sleep(delay).await;
+            &response.body
+        }
+        _ => &response.body
+    };
+
+    let stream = stream.get_mut();
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(body.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+async fn read_request(stream: &mut BufReader<TcpStream>) -> io::Result<Option<Request>> {
+    let mut line = String::new();
+
+    if stream.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    let mut headers = HashMap::new();
+    let mut cookies = HashMap::new();
+
+    loop {
+        line.clear();
+
+        if stream.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        let name = name.trim().to_ascii_lowercase();
+        headers.insert(name.clone(), value.trim().to_string());
+
+        match name.as_str() {
+            "content-length" => content_length = value.trim().parse().unwrap_or(0),
+            "cookie" => cookies.extend(
+                value.split(';')
+                    .filter_map(|cookie| cookie.trim().split_once('='))
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+            ),
+            _ => {}
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    stream.read_exact(&mut body).await?;
+
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+
+    // qBittorrent accepts parameters both in the query and in a form encoded body
+    let params = url::form_urlencoded::parse(query.as_bytes())
+        .chain(url::form_urlencoded::parse(&body))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+
+    Ok(Some(Request {
+        method,
+        path: path.to_string(),
+        params,
+        headers,
+        cookies
+    }))
+}
+
+impl MockState {
+    fn handle(&mut self, req: &Request) -> Response {
+        *self.requests.entry(req.path.clone()).or_default() += 1;
+
+        let proxy_authorized = self.required_headers.iter()
+            .all(|(name, value)| req.headers.get(name) == Some(value));
+
+        if !proxy_authorized {
+            return Response::status(401, "Unauthorized");
+        }
+
+        let fault = match self.failures.get_mut(&req.path) {
+            Some((fault, count)) if *count > 0 => {
+                *count -= 1;
+                Some(*fault)
+            }
+            _ => None
+        };
+
+        if let Some(Fault::Status(status)) = fault {
+            return Response::status(status, "Injected failure");
+        }
+
+        Response {
+            fault,
+            ..self.respond(req)
+        }
+    }
+
+    fn respond(&mut self, req: &Request) -> Response {
+        let Some(endpoint) = req.path.strip_prefix("/api/v2/") else {
+            return Response::status(404, "Not Found");
+        };
+
+        if endpoint == "auth/login" {
+            return self.login(req);
+        }
+
+        let authenticated = req.cookies.get("SID").is_some_and(|sid| self.sessions.contains(sid));
+
+        if !authenticated {
+            return Response::status(403, "Forbidden");
+        }
+
+        let post_only = !matches!(endpoint,
+            "app/version" | "app/webapiVersion" | "app/buildInfo" | "app/preferences" | "app/defaultSavePath" |
+            "log/main" | "log/peers" | "sync/maindata" | "transfer/info" | "transfer/speedLimitsMode" |
+            "transfer/downloadLimit" | "transfer/uploadLimit" | "torrents/info" | "torrents/properties" |
+            "torrents/trackers" | "torrents/webseeds"
+        );
+
+        if post_only && req.method != "POST" {
+            return Response::status(405, "Method Not Allowed");
+        }
+
+        let start_stop = self.api_version.parse::<ApiVersion>()
+            .is_ok_and(|version| version >= ApiVersion::new(2, 11, 0));
+
+        match endpoint {
+            "auth/logout" => {
+                if let Some(sid) = req.cookies.get("SID") {
+                    self.sessions.remove(sid);
+                }
+
+                Response::ok()
+            }
+            "app/version" => Response::text(&self.app_version),
+            "app/webapiVersion" => Response::text(&self.api_version),
+            "app/buildInfo" => Response::json(json!({
+                "bitness": 64,
+                "boost": "1.83.0",
+                "libtorrent": "2.0.10.0",
+                "openssl": "3.1.4",
+                "platform": "linux",
+                "qt": "6.6.1",
+                "zlib": "1.3"
+            })),
+            "app/shutdown" => Response::ok(),
+            "app/preferences" => Response::json(Value::Object(self.preferences.clone())),
+            "app/setPreferences" => self.set_preferences(req),
+            "app/defaultSavePath" => Response::text(self.preferences["save_path"].as_str().unwrap_or_default()),
+            "log/main" => self.logs(req),
+            "log/peers" => {
+                let last_known_id = param_i64(req, "last_known_id").unwrap_or(-1);
+                Response::json(Value::Array(newer_than(&self.peer_logs, last_known_id)))
+            }
+            "sync/maindata" => self.main_data(req),
+            "transfer/info" => Response::json(self.server_state()),
+            "transfer/speedLimitsMode" => Response::text(if self.alt_speed_enabled { "1" } else { "0" }),
+            "transfer/toggleSpeedLimitsMode" => {
+                self.alt_speed_enabled = !self.alt_speed_enabled;
+                Response::ok()
+            }
+            "transfer/downloadLimit" => Response::text(self.active_limits().0),
+            "transfer/uploadLimit" => Response::text(self.active_limits().1),
+            "transfer/setDownloadLimit" | "transfer/setUploadLimit" => self.set_limit(req, endpoint == "transfer/setDownloadLimit"),
+            "transfer/banPeers" => {
+                let peers = req.params.get("peers").cloned().unwrap_or_default();
+                self.banned_peers.extend(peers.split('|').filter(|peer| !peer.is_empty()).map(str::to_string));
+                Response::ok()
+            }
+            "torrents/info" => self.torrent_list(req),
+            "torrents/properties" => self.with_torrent(req, torrent_properties),
+            "torrents/trackers" => self.with_torrent(req, |_| json!([])),
+            "torrents/webseeds" => self.with_torrent(req, |_| json!([])),
+            "torrents/pause" | "torrents/resume" if !start_stop => self.set_torrent_state(req, endpoint == "torrents/pause", "paused"),
+            "torrents/stop" | "torrents/start" if start_stop => self.set_torrent_state(req, endpoint == "torrents/stop", "stopped"),
+            "torrents/delete" => {
+                let hashes = self.selected_hashes(req);
+                self.torrents.retain(|hash, _| !hashes.contains(hash));
+                Response::ok()
+            }
+            _ => Response::status(404, "Not Found")
+        }
+    }
+
+    fn login(&mut self, req: &Request) -> Response {
+        if self.auth_failures >= MAX_AUTH_FAIL_COUNT {
+            return Response::status(403, "Your IP address has been banned after too many failed authentication attempts.");
+        }
+
+        let username = req.params.get("username").map(String::as_str);
+        let password = req.params.get("password").map(String::as_str);
+
+        if username != Some(self.username.as_str()) || password != Some(self.password.as_str()) {
+            self.auth_failures += 1;
+            return Response::text("Fails.");
+        }
+
+        self.auth_failures = 0;
+
+        let sid = format!("{:016x}{:016x}", random(), random());
+        self.sessions.insert(sid.clone());
+
+        Response {
+            set_cookie: Some(format!("SID={}; HttpOnly; SameSite=Strict; path=/", sid)),
+            ..Response::text("Ok.")
+        }
+    }
+
+    fn set_preferences(&mut self, req: &Request) -> Response {
+        let Some(Ok(Value::Object(prefs))) = req.params.get("json").map(|json| serde_json::from_str(json)) else {
+            return Response::status(400, "Bad Request");
+        };
+
+        self.preferences.extend(prefs);
+        Response::ok()
+    }
+
+    fn logs(&self, req: &Request) -> Response {
+        let enabled = |name: &str| req.params.get(name).is_none_or(|value| value == "true");
+        let last_known_id = param_i64(req, "last_known_id").unwrap_or(-1);
+
+        let levels = [
+            (LogLevel::Normal, enabled("normal")),
+            (LogLevel::Info, enabled("info")),
+            (LogLevel::Warning, enabled("warning")),
+            (LogLevel::Critical, enabled("critical"))
+        ];
+
+        // types unknown to the filter (LogLevel::Other) are always returned
+        let logs = newer_than(&self.logs, last_known_id).into_iter()
+            .filter(|log| {
+                let level = levels.iter().find(|&&(level, _)| log["type"] == u8::from(level));
+                level.is_none_or(|&(_, enabled)| enabled)
+            })
+            .collect();
+
+        Response::json(Value::Array(logs))
+    }
+
+    // Like qBittorrent: a full update for rid 0 or an unknown rid, otherwise only the changed fields
+    // of torrents, categories and server_state, and keys without changes are left out
+    fn main_data(&mut self, req: &Request) -> Response {
+        let rid = param_i64(req, "rid").unwrap_or(0) as u64;
+
+        let current = Snapshot {
+            // maindata torrents are keyed by hash, without a hash field
+            torrents: self.torrents.iter()
+                .map(|(hash, torrent)| {
+                    let mut torrent = torrent.clone();
+                    if let Value::Object(fields) = &mut torrent {
+                        fields.remove("hash");
+                    }
+
+                    (hash.clone(), torrent)
+                })
+                .collect(),
+            categories: self.categories.clone(),
+            tags: self.tags.clone(),
+            server_state: self.server_state()
+        };
+
+        let previous = self.snapshots.iter()
+            .find(|(snapshot_rid, _)| rid != 0 && *snapshot_rid == rid)
+            .map(|(_, snapshot)| snapshot);
+
+        let mut data = Map::new();
+
+        match previous {
+            None => {
+                let mut tags = current.tags.iter().collect::<Vec<_>>();
+                tags.sort();
+
+                data.insert("full_update".to_string(), json!(true));
+                data.insert("torrents".to_string(), json!(current.torrents));
+                data.insert("categories".to_string(), json!(current.categories));
+                data.insert("tags".to_string(), json!(tags));
+                data.insert("server_state".to_string(), current.server_state.clone());
+            }
+            Some(previous) => {
+                let (torrents, torrents_removed) = delta(&previous.torrents, &current.torrents);
+                let (categories, categories_removed) = delta(&previous.categories, &current.categories);
+
+                let mut tags = current.tags.difference(&previous.tags).collect::<Vec<_>>();
+                let mut tags_removed = previous.tags.difference(&current.tags).collect::<Vec<_>>();
+                tags.sort();
+                tags_removed.sort();
+
+                let changes = [
+                    ("torrents", json!(torrents)),
+                    ("torrents_removed", json!(torrents_removed)),
+                    ("categories", json!(categories)),
+                    ("categories_removed", json!(categories_removed)),
+                    ("tags", json!(tags)),
+                    ("tags_removed", json!(tags_removed)),
+                    ("server_state", changed_fields(&previous.server_state, &current.server_state))
+                ];
+
+                data.extend(changes.into_iter()
+                    .filter(|(_, value)| *value != json!({}) && *value != json!([]))
+                    .map(|(key, value)| (key.to_string(), value)));
+            }
+        }
+
+        self.rid += 1;
+        data.insert("rid".to_string(), json!(self.rid));
+
+        self.snapshots.push_back((self.rid, current));
+
+        if self.snapshots.len() > MAX_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+
+        Response::json(Value::Object(data))
+    }
+
+    fn server_state(&self) -> Value {
+        let (dl_limit, up_limit) = self.active_limits();
+        let torrents = self.torrents.values();
+
+        json!({
+            "dl_info_speed": torrents.clone().filter_map(|torrent| torrent["dlspeed"].as_u64()).sum::<u64>(),
+            "dl_info_data": torrents.clone().filter_map(|torrent| torrent["downloaded_session"].as_u64()).sum::<u64>(),
+            "up_info_speed": torrents.clone().filter_map(|torrent| torrent["upspeed"].as_u64()).sum::<u64>(),
+            "up_info_data": torrents.filter_map(|torrent| torrent["uploaded_session"].as_u64()).sum::<u64>(),
+            "dl_rate_limit": dl_limit,
+            "up_rate_limit": up_limit,
+            "dht_nodes": 300,
+            "connection_status": "connected",
+            "use_alt_speed_limits": self.alt_speed_enabled
+        })
+    }
+
+    fn active_limits(&self) -> (u64, u64) {
+        if self.alt_speed_enabled {
+            (self.alt_dl_limit, self.alt_up_limit)
+        } else {
+            (self.dl_limit, self.up_limit)
+        }
+    }
+
+    // like qBittorrent, changes the limit of the active mode
+    fn set_limit(&mut self, req: &Request, download: bool) -> Response {
+        let Some(limit) = param_i64(req, "limit") else {
+            return Response::status(400, "Bad Request");
+        };
+
+        let limit = limit.max(0) as u64;
+
+        match (self.alt_speed_enabled, download) {
+            (false, true) => self.dl_limit = limit,
+            (false, false) => self.up_limit = limit,
+            (true, true) => self.alt_dl_limit = limit,
+            (true, false) => self.alt_up_limit = limit
+        }
+
+        Response::ok()
+    }
+
+    fn torrent_list(&self, req: &Request) -> Response {
+        let filter = req.params.get("filter").map_or("all", String::as_str);
+        let hashes = req.params.get("hashes").map(|hashes| hashes.split('|').collect::<HashSet<_>>());
+
+        let mut torrents = self.torrents.values()
+            .filter(|torrent| matches_filter(torrent, filter))
+            .filter(|torrent| req.params.get("category").is_none_or(|category| torrent["category"] == category.as_str()))
+            .filter(|torrent| req.params.get("tag").is_none_or(|tag| {
+                let tags = torrent["tags"].as_str().unwrap_or_default();
+
+                if tag.is_empty() {
+                    tags.is_empty()
+                } else {
+                    tags.split(", ").any(|torrent_tag| torrent_tag == tag)
+                }
+            }))
+            .filter(|torrent| hashes.as_ref().is_none_or(|hashes| hashes.contains(torrent["hash"].as_str().unwrap_or_default())))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if let Some(sort) = req.params.get("sort") {
+            torrents.sort_by(|a, b| compare_values(&a[sort.as_str()], &b[sort.as_str()]));
+        }
+
+        if req.params.get("reverse").is_some_and(|reverse| reverse == "true") {
+            torrents.reverse();
+        }
+
+        let len = torrents.len() as i64;
+        let offset = param_i64(req, "offset").unwrap_or(0);
+        let offset = if offset < 0 { (len + offset).max(0) } else { offset.min(len) } as usize;
+        let limit = param_i64(req, "limit").filter(|&limit| limit > 0).map_or(usize::MAX, |limit| limit as usize);
+
+        Response::json(Value::Array(torrents.into_iter().skip(offset).take(limit).collect()))
+    }
+
+    fn with_torrent<F: Fn(&Value) -> Value>(&self, req: &Request, f: F) -> Response {
+        match req.params.get("hash").and_then(|hash| self.torrents.get(hash)) {
+            Some(torrent) => Response::json(f(torrent)),
+            None => Response::status(404, "Not Found")
+        }
+    }
+
+    fn selected_hashes(&self, req: &Request) -> HashSet<String> {
+        match req.params.get("hashes").map(String::as_str) {
+            Some("all") => self.torrents.keys().cloned().collect(),
+            Some(hashes) => hashes.split('|').map(str::to_string).collect(),
+            None => HashSet::new()
+        }
+    }
+
+    fn set_torrent_state(&mut self, req: &Request, stop: bool, stopped_prefix: &str) -> Response {
+        let hashes = self.selected_hashes(req);
+
+        for (hash, torrent) in self.torrents.iter_mut() {
+            if !hashes.contains(hash) {
+                continue;
+            }
+
+            let suffix = if torrent["progress"].as_f64() == Some(1.0) { "UP" } else { "DL" };
+            let state = if stop {
+                format!("{}{}", stopped_prefix, suffix)
+            } else if suffix == "UP" {
+                "stalledUP".to_string()
+            } else {
+                "stalledDL".to_string()
+            };
+
+            torrent["state"] = json!(state);
+        }
+
+        Response::ok()
+    }
+}
+
+fn torrent_properties(torrent: &Value) -> Value {
+    json!({
+        "save_path": torrent["save_path"],
+        "creation_date": torrent["added_on"],
+        "piece_size": 4194304,
+        "comment": "",
+        "total_wasted": 0,
+        "total_uploaded": torrent["uploaded"],
+        "total_uploaded_session": torrent["uploaded_session"],
+        "total_downloaded": torrent["downloaded"],
+        "total_downloaded_session": torrent["downloaded_session"],
+        "up_limit": torrent["up_limit"],
+        "dl_limit": torrent["dl_limit"],
+        "time_elapsed": torrent["time_active"],
+        "seeding_time": torrent["seeding_time"],
+        "nb_connections": 0,
+        "nb_connections_limit": 100,
+        "share_ratio": torrent["ratio"],
+        "addition_date": torrent["added_on"],
+        "completion_date": torrent["completion_on"],
+        "created_by": "mktorrent 1.1",
+        "dl_speed_avg": 0,
+        "dl_speed": torrent["dlspeed"],
+        "eta": torrent["eta"],
+        "last_seen": torrent["seen_complete"],
+        "peers": torrent["num_leechs"],
+        "peers_total": torrent["num_incomplete"],
+        "pieces_have": 256,
+        "pieces_num": 256,
+        "reannounce": 1200,
+        "seeds": torrent["num_seeds"],
+        "seeds_total": torrent["num_complete"],
+        "total_size": torrent["size"],
+        "up_speed_avg": 0,
+        "up_speed": torrent["upspeed"],
+        "isPrivate": false
+    })
+}
+
+fn matches_filter(torrent: &Value, filter: &str) -> bool {
+    let state = torrent["state"].as_str().unwrap_or_default();
+    let downloading = matches!(state, "downloading" | "metaDL" | "forcedMetaDL" | "stalledDL" | "checkingDL" | "pausedDL" | "stoppedDL" | "queuedDL" | "forcedDL" | "allocating");
+    let seeding = matches!(state, "uploading" | "stalledUP" | "checkingUP" | "queuedUP" | "forcedUP");
+    let paused = state.starts_with("paused") || state.starts_with("stopped");
+    let active = torrent["dlspeed"].as_u64().unwrap_or(0) > 0 || torrent["upspeed"].as_u64().unwrap_or(0) > 0;
+
+    match filter {
+        "downloading" => downloading,
+        "seeding" => seeding,
+        "completed" => torrent["progress"].as_f64() == Some(1.0),
+        "paused" | "stopped" => paused,
+        "resumed" | "running" => !paused,
+        "active" => active,
+        "inactive" => !active,
+        "stalled" => state.starts_with("stalled"),
+        "stalled_uploading" => state == "stalledUP",
+        "stalled_downloading" => state == "stalledDL",
+        "errored" => matches!(state, "error" | "missingFiles"),
+        _ => true
+    }
+}
+
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => Ordering::Equal
+    }
+}
+
+// changed fields of existing objects, added objects in full, and removed keys
+fn delta(previous: &BTreeMap<String, Value>, current: &BTreeMap<String, Value>) -> (Map<String, Value>, Vec<String>) {
+    let changed = current.iter()
+        .filter_map(|(key, value)| match previous.get(key) {
+            Some(previous) if previous == value => None,
+            Some(previous) => Some((key.clone(), changed_fields(previous, value))),
+            None => Some((key.clone(), value.clone()))
+        })
+        .collect();
+
+    let removed = previous.keys()
+        .filter(|key| !current.contains_key(*key))
+        .cloned()
+        .collect();
+
+    (changed, removed)
+}
+
+fn changed_fields(previous: &Value, current: &Value) -> Value {
+    let changed = current.as_object().into_iter()
+        .flatten()
+        .filter(|(key, value)| previous.get(key.as_str()) != Some(*value))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+
+    Value::Object(changed)
+}
+
+fn newer_than(logs: &[Value], last_known_id: i64) -> Vec<Value> {
+    logs.iter()
+        .filter(|log| log["id"].as_i64().unwrap_or(0) > last_known_id)
+        .cloned()
+        .collect()
+}
+
+fn param_i64(req: &Request, name: &str) -> Option<i64> {
+    req.params.get(name).and_then(|value| value.parse().ok())
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs())
+}
+
+fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
@@ -0,0 +1,312 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    str::FromStr
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::QBittorrentError;
+
+// WebAPI version as reported by /api/v2/app/webapiVersion, e.g. "2.9.3"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ApiVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32
+}
+
+impl ApiVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        ApiVersion { major, minor, patch }
+    }
+
+    // req is a comma separated list of comparisons, e.g. ">=2.8.0" or ">=2.8, <2.11"
+    pub fn satisfies(&self, req: &str) -> Result<bool, QBittorrentError> {
+        satisfies(self, req)
+    }
+}
+
+impl FromStr for ApiVersion {
+    type Err = QBittorrentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split('.');
+
+        let major = parts.next().unwrap_or_default().parse()?;
+        let minor = parts.next().map_or(Ok(0), str::parse)?;
+        let patch = parts.next().map_or(Ok(0), str::parse)?;
+
+        if parts.next().is_some() {
+            return Err(QBittorrentError::BadInput(format!("invalid api version: {}", s)));
+        }
+
+        Ok(ApiVersion { major, minor, patch })
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl TryFrom<String> for ApiVersion {
+    type Error = QBittorrentError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ApiVersion> for String {
+    fn from(version: ApiVersion) -> String {
+        version.to_string()
+    }
+}
+
+// qBittorrent version as reported by /api/v2/app/version, e.g. "v4.6.5", "v4.3.4.1" or "v5.0.0beta1"
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct AppVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    // fourth component of bugfix re-releases like v4.3.4.1, 0 if missing
+    pub build: u32,
+    // pre-release part like "beta1" or "rc1", ordered before the release itself
+    pub pre: Option<String>
+}
+
+impl AppVersion {
+    pub fn satisfies(&self, req: &str) -> Result<bool, QBittorrentError> {
+        satisfies(self, req)
+    }
+}
+
+impl FromStr for AppVersion {
+    type Err = QBittorrentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let version = s.trim().trim_start_matches('v');
+        let numeric_len = version
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(version.len());
+        let (numeric, pre) = version.split_at(numeric_len);
+        let invalid = || QBittorrentError::BadInput(format!("invalid application version: {}", s));
+
+        let mut parts = numeric.trim_end_matches('.').split('.');
+        let mut next_part = |required: bool| match parts.next() {
+            Some(part) => part.parse::<u32>().map_err(|_| invalid()),
+            None if required => Err(invalid()),
+            None => Ok(0)
+        };
+
+        let major = next_part(true)?;
+        let minor = next_part(false)?;
+        let patch = next_part(false)?;
+        let build = next_part(false)?;
+
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        let pre = pre.trim_start_matches(['-', '.']);
+
+        Ok(AppVersion {
+            major,
+            minor,
+            patch,
+            build,
+            pre: if pre.is_empty() { None } else { Some(pre.to_string()) }
+        })
+    }
+}
+
+impl Ord for AppVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch, self.build)
+            .cmp(&(other.major, other.minor, other.patch, other.build))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                // the raw string breaks ties like beta01 and beta1, keeping Ord consistent with Eq
+                (Some(pre), Some(other_pre)) => pre_release_key(pre)
+                    .cmp(&pre_release_key(other_pre))
+                    .then_with(|| pre.cmp(other_pre))
+            })
+    }
+}
+
+// "beta10" -> ("beta", Some(10), ""), so beta2 < beta10 < rc1
+fn pre_release_key(pre: &str) -> (&str, Option<u64>, &str) {
+    let prefix_len = pre.find(|c: char| c.is_ascii_digit()).unwrap_or(pre.len());
+    let (prefix, rest) = pre.split_at(prefix_len);
+    let number_len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    let (number, suffix) = rest.split_at(number_len);
+
+    (prefix, number.parse().ok(), suffix)
+}
+
+impl PartialOrd for AppVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for AppVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "v{}.{}.{}", self.major, self.minor, self.patch)?;
+
+        if self.build != 0 {
+            write!(f, ".{}", self.build)?;
+        }
+
+        if let Some(ref pre) = self.pre {
+            write!(f, "{}", pre)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for AppVersion {
+    type Error = QBittorrentError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<AppVersion> for String {
+    fn from(version: AppVersion) -> String {
+        version.to_string()
+    }
+}
+
+fn satisfies<V>(version: &V, req: &str) -> Result<bool, QBittorrentError>
+where
+    V: FromStr<Err = QBittorrentError> + Ord
+{
+    for comparison in req.split(',') {
+        let comparison = comparison.trim();
+        let op_len = comparison
+            .find(|c: char| !matches!(c, '<' | '>' | '='))
+            .unwrap_or(comparison.len());
+        let (op, other) = comparison.split_at(op_len);
+        let ordering = version.cmp(&other.trim().parse::<V>()?);
+
+        let matches = match op {
+            ">=" => ordering != Ordering::Less,
+            ">" => ordering == Ordering::Greater,
+            "<=" => ordering != Ordering::Greater,
+            "<" => ordering == Ordering::Less,
+            "" | "=" | "==" => ordering == Ordering::Equal,
+            _ => return Err(QBittorrentError::BadInput(format!("invalid version requirement: {}", req)))
+        };
+
+        if !matches {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+// Features of the connected server which depend on its WebAPI version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    pub api_version: ApiVersion,
+    // torrents/pause and torrents/resume were renamed to torrents/stop and torrents/start (qBittorrent 5.0)
+    pub start_stop_endpoints: bool,
+    // inactive_seeding_time_limit share limit (qBittorrent 4.6)
+    pub inactive_seeding_time_limit: bool,
+    // tag parameter of torrents/info
+    pub torrent_list_tag_filter: bool
+}
+
+impl Capabilities {
+    pub fn for_version(api_version: ApiVersion) -> Self {
+        Capabilities {
+            api_version,
+            start_stop_endpoints: api_version >= ApiVersion::new(2, 11, 0),
+            inactive_seeding_time_limit: api_version >= ApiVersion::new(2, 9, 2),
+            torrent_list_tag_filter: api_version >= ApiVersion::new(2, 8, 3)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_version(s: &str) -> AppVersion {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_app_versions() {
+        let version = app_version("v4.6.5");
+        assert_eq!((version.major, version.minor, version.patch, version.pre), (4, 6, 5, None));
+
+        let version = app_version("v5.0.0rc1");
+        assert_eq!((version.major, version.minor, version.patch), (5, 0, 0));
+        assert_eq!(version.pre.as_deref(), Some("rc1"));
+        assert_eq!(version.to_string(), "v5.0.0rc1");
+
+        let version = app_version("v4.3.4.1");
+        assert_eq!((version.major, version.minor, version.patch, version.build), (4, 3, 4, 1));
+        assert_eq!(version.to_string(), "v4.3.4.1");
+        assert!(app_version("v4.3.4") < version && version < app_version("v4.3.5"));
+
+        assert!("vfoo".parse::<AppVersion>().is_err());
+        assert!("v4.3.4.1.2".parse::<AppVersion>().is_err());
+    }
+
+    #[test]
+    fn parses_api_versions() {
+        assert_eq!("2.9.3".parse::<ApiVersion>().unwrap(), ApiVersion::new(2, 9, 3));
+        assert_eq!("2.8".parse::<ApiVersion>().unwrap(), ApiVersion::new(2, 8, 0));
+        assert!("2.8.0.1".parse::<ApiVersion>().is_err());
+        assert!("".parse::<ApiVersion>().is_err());
+    }
+
+    #[test]
+    fn orders_pre_releases() {
+        assert!(app_version("v5.0.0beta2") < app_version("v5.0.0beta10"));
+        assert!(app_version("v5.0.0beta10") < app_version("v5.0.0rc1"));
+        assert!(app_version("v5.0.0rc1") < app_version("v5.0.0"));
+        assert!(app_version("v4.6.5") < app_version("v5.0.0alpha1"));
+
+        let (beta01, beta1) = (app_version("v5.0.0beta01"), app_version("v5.0.0beta1"));
+        assert_ne!(beta01, beta1);
+        assert_ne!(beta01.cmp(&beta1), Ordering::Equal);
+        assert!(beta01 < app_version("v5.0.0beta2"));
+    }
+
+    #[test]
+    fn checks_requirements() {
+        let version = ApiVersion::new(2, 9, 3);
+
+        assert!(version.satisfies(">=2.8, <2.11").unwrap());
+        assert!(!version.satisfies(">=2.8, <2.9").unwrap());
+        assert!(version.satisfies("2.9.3").unwrap());
+        assert!(version.satisfies("==2.9.3").unwrap());
+        assert!(!version.satisfies(">2.9.3").unwrap());
+
+        assert!(app_version("v5.0.0rc1").satisfies("<v5.0.0").unwrap());
+        assert!(app_version("v5.0.0beta10").satisfies(">v5.0.0beta2").unwrap());
+    }
+
+    #[test]
+    fn rejects_bad_requirements() {
+        let version = ApiVersion::new(2, 9, 3);
+
+        assert!(version.satisfies("=>2.8").is_err());
+        assert!(version.satisfies("<>2.8").is_err());
+        assert!(version.satisfies("~2.8").is_err());
+        assert!(version.satisfies(">=2.8,").is_err());
+    }
+}
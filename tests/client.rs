@@ -0,0 +1,480 @@
+use std::{pin::pin, time::Duration};
+
+use futures_util::StreamExt;
+use qbittorrent_client::{
+    models::{
+        AppPreferences, CategoryFilter, LogLevel, LogParams, TagFilter, Torrent, TorrentListFilter, TorrentListParams,
+        TorrentSortField
+    },
+    testing::{mock_torrent, MockServer, MOCK_PASSWORD, MOCK_USERNAME},
+    BandwidthScheduler, ProxyAuth, QBittorrentClient, QBittorrentError, RetryPolicy, SpeedLimits
+};
+use serde_json::json;
+
+const HASH_A: &str = "8c212779b4abde7c6bc608063a0d008b7e40ce32";
+const HASH_B: &str = "3e8a0a1c5f0c2b4d6e8f0a1b2c3d4e5f6a7b8c9d";
+const HASH_C: &str = "c9a7d1f0e2b3a4c5d6e7f8091a2b3c4d5e6f7081";
+
+async fn logged_in(server: &MockServer) -> QBittorrentClient {
+    let client = QBittorrentClient::new(server.url()).await.unwrap();
+    client.login(MOCK_USERNAME, MOCK_PASSWORD).await.unwrap();
+    client
+}
+
+#[tokio::test]
+async fn login_and_version() {
+    let server = MockServer::start().await.unwrap();
+    let client = logged_in(&server).await;
+
+    assert_eq!(client.application_version().await.unwrap().to_string(), "v4.6.5");
+    assert_eq!(client.api_version().await.unwrap().to_string(), "2.9.3");
+    assert_eq!(client.build_info().await.unwrap().platform.as_deref(), Some("linux"));
+
+    let build_info = client.build_info().await.unwrap();
+    assert_eq!(build_info.version.map(|version| version.to_string()).as_deref(), Some("v4.6.5"));
+
+    server.set_versions("v4.3.4.1", "2.8.2");
+    assert_eq!(client.application_version().await.unwrap().to_string(), "v4.3.4.1");
+
+    // a failing version request leaves the version out instead of failing the build info
+    server.fail_next("/api/v2/app/version", 500, 1);
+    let build_info = client.build_info().await.unwrap();
+    assert_eq!((build_info.platform.as_deref(), build_info.version), (Some("linux"), None));
+}
+
+#[tokio::test]
+async fn login_with_invalid_credentials() {
+    let server = MockServer::start().await.unwrap();
+    let client = QBittorrentClient::new(server.url()).await.unwrap();
+
+    let err = client.login(MOCK_USERNAME, "wrong").await.unwrap_err();
+    assert!(matches!(err, QBittorrentError::InvalidCredentials), "{:?}", err);
+
+    for _ in 0..5 {
+        let _ = client.login(MOCK_USERNAME, "wrong").await;
+    }
+
+    let err = client.login(MOCK_USERNAME, MOCK_PASSWORD).await.unwrap_err();
+    assert!(matches!(err, QBittorrentError::IpBanned), "{:?}", err);
+}
+
+#[tokio::test]
+async fn login_with_special_characters() {
+    let server = MockServer::start().await.unwrap();
+    server.set_credentials("user&name", "p@ss=w+rd &%");
+
+    let client = QBittorrentClient::new(server.url()).await.unwrap();
+    client.login("user&name", "p@ss=w+rd &%").await.unwrap();
+}
+
+#[tokio::test]
+async fn unauthenticated_requests_are_forbidden() {
+    let server = MockServer::start().await.unwrap();
+    let client = QBittorrentClient::new(server.url()).await.unwrap();
+
+    let err = client.application_version().await.unwrap_err();
+    assert_eq!(err.status_code(), Some(403));
+}
+
+#[test]
+fn proxy_auth_debug_redacts_secrets() {
+    let basic = ProxyAuth::Basic { username: "admin".to_string(), password: Some("hunter2".to_string()) };
+    let header = ProxyAuth::Header { name: "X-Api-Key".to_string(), value: "s3cr3t".to_string() };
+
+    let basic = format!("{:?}", basic);
+    let header = format!("{:?}", header);
+
+    assert!(basic.contains("admin") && !basic.contains("hunter2"), "{}", basic);
+    assert!(header.contains("X-Api-Key") && !header.contains("s3cr3t"), "{}", header);
+}
+
+#[tokio::test]
+async fn proxy_auth_is_sent_with_every_request() {
+    let server = MockServer::start().await.unwrap();
+    // "user:pass"
+    server.require_header("Authorization", "Basic dXNlcjpwYXNz");
+
+    let client = QBittorrentClient::new(server.url()).await.unwrap();
+    let err = client.login(MOCK_USERNAME, MOCK_PASSWORD).await.unwrap_err();
+    assert!(matches!(err, QBittorrentError::AuthFailed), "{:?}", err);
+
+    client.set_proxy_auth(Some(ProxyAuth::Basic { username: "user".to_string(), password: Some("pass".to_string()) })).await;
+    client.login(MOCK_USERNAME, MOCK_PASSWORD).await.unwrap();
+    client.application_version().await.unwrap();
+
+    let server = MockServer::start().await.unwrap();
+    server.require_header("X-Api-Key", "s3cr3t");
+
+    let client = QBittorrentClient::new(server.url()).await.unwrap();
+    client.set_proxy_auth(Some(ProxyAuth::Header { name: "X-Api-Key".to_string(), value: "s3cr3t".to_string() })).await;
+    client.login(MOCK_USERNAME, MOCK_PASSWORD).await.unwrap();
+    client.application_version().await.unwrap();
+
+    client.set_proxy_auth(None).await;
+    assert_eq!(client.application_version().await.unwrap_err().status_code(), Some(401));
+}
+
+#[tokio::test]
+async fn relogin_after_session_expiry() {
+    let server = MockServer::start().await.unwrap();
+    let client = QBittorrentClient::new(server.url()).await.unwrap();
+    client.set_credentials(MOCK_USERNAME, MOCK_PASSWORD).await;
+    client.login(MOCK_USERNAME, MOCK_PASSWORD).await.unwrap();
+
+    server.expire_sessions();
+
+    assert!(client.application_version().await.is_ok());
+    assert_eq!(server.request_count("/api/v2/auth/login"), 2);
+}
+
+#[tokio::test]
+async fn torrent_list_filters_and_sorts() {
+    let server = MockServer::start().await.unwrap();
+    server.add_torrent(mock_torrent(HASH_A, "b.iso"));
+    server.add_torrent(mock_torrent(HASH_B, "a.iso"));
+
+    let mut downloading = mock_torrent(HASH_C, "c.iso");
+    downloading["state"] = json!("downloading");
+    downloading["progress"] = json!(0.25);
+    server.add_torrent(downloading);
+
+    let client = logged_in(&server).await;
+
+    let torrents = client.torrent_list(TorrentListParams::new().sort_by(TorrentSortField::Name)).await.unwrap();
+    let names = torrents.iter().map(|torrent| torrent.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["a.iso", "b.iso", "c.iso"]);
+
+    server.update_torrent(HASH_B, json!({ "time_active": 10 }));
+    let torrents = client.torrent_list(TorrentListParams::new().sort_by(TorrentSortField::TimeActive)).await.unwrap();
+    assert_eq!(torrents[0].hash, HASH_B);
+
+    let params = TorrentListParams::new()
+        .filter(TorrentListFilter::Seeding)
+        .sort_by(TorrentSortField::Name)
+        .reverse()
+        .page(1, 0);
+    let torrents = client.torrent_list(params).await.unwrap();
+    assert_eq!(torrents.len(), 1);
+    assert_eq!(torrents[0].hash, HASH_A);
+
+    let torrents = client.torrent_list(TorrentListParams::new().filter(TorrentListFilter::Downloading)).await.unwrap();
+    assert_eq!(torrents.len(), 1);
+    assert_eq!(torrents[0].hash, HASH_C);
+}
+
+#[tokio::test]
+async fn torrent_list_category_and_tag_filters() {
+    let server = MockServer::start().await.unwrap();
+
+    let mut movies = mock_torrent(HASH_A, "a.mkv");
+    movies["category"] = json!("Filme & Serien ü");
+    movies["tags"] = json!("4k, neu");
+    server.add_torrent(movies);
+    server.add_torrent(mock_torrent(HASH_B, "b.iso"));
+
+    let client = logged_in(&server).await;
+
+    let hashes = |torrents: Vec<Torrent>| torrents.into_iter().map(|torrent| torrent.hash).collect::<Vec<_>>();
+
+    let all = client.torrent_list(TorrentListParams::new()).await.unwrap();
+    assert_eq!(all.len(), 2);
+
+    let named = client.torrent_list(TorrentListParams::new().category("Filme & Serien ü")).await.unwrap();
+    assert_eq!(hashes(named), [HASH_A]);
+
+    let uncategorized = client.torrent_list(TorrentListParams::new().category(CategoryFilter::Uncategorized)).await.unwrap();
+    assert_eq!(hashes(uncategorized), [HASH_B]);
+
+    let tagged = client.torrent_list(TorrentListParams::new().tag("neu")).await.unwrap();
+    assert_eq!(hashes(tagged), [HASH_A]);
+
+    let untagged = client.torrent_list(TorrentListParams::new().tag(TagFilter::Untagged)).await.unwrap();
+    assert_eq!(hashes(untagged), [HASH_B]);
+}
+
+#[tokio::test]
+async fn torrent_list_paged() {
+    let server = MockServer::start().await.unwrap();
+
+    for i in 0..25 {
+        let mut torrent = mock_torrent(&format!("{:040x}", i), &format!("{:02}.iso", i));
+        torrent["added_on"] = json!(1700000000 + i);
+        server.add_torrent(torrent);
+    }
+
+    let client = logged_in(&server).await;
+
+    let torrents = client.torrent_list_paged(TorrentListParams::new(), 10, false)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(torrents.len(), 25);
+    assert_eq!(server.request_count("/api/v2/torrents/info"), 3);
+
+    // a torrent added mid-scan doesn't shift the remaining pages
+    let mut torrents = pin!(client.torrent_list_paged(TorrentListParams::new().sort_by(TorrentSortField::Name), 10, true));
+    let mut hashes = Vec::new();
+
+    for _ in 0..10 {
+        hashes.push(torrents.next().await.unwrap().unwrap().hash);
+    }
+
+    let mut added = mock_torrent(HASH_A, "00-new.iso");
+    added["added_on"] = json!(1800000000);
+    server.add_torrent(added);
+
+    while let Some(torrent) = torrents.next().await {
+        hashes.push(torrent.unwrap().hash);
+    }
+
+    assert_eq!(hashes.len(), 26);
+    assert_eq!(hashes.last().map(String::as_str), Some(HASH_A));
+}
+
+#[tokio::test]
+async fn torrent_properties_of_unknown_hash() {
+    let server = MockServer::start().await.unwrap();
+    server.add_torrent(mock_torrent(HASH_A, "a.iso"));
+    let client = logged_in(&server).await;
+
+    let props = client.torrent_generic_properties(HASH_A).await.unwrap();
+    assert_eq!(props.save_path, "/downloads");
+
+    let err = client.torrent_generic_properties(HASH_B).await.unwrap_err();
+    assert!(matches!(err, QBittorrentError::NotFound), "{:?}", err);
+}
+
+#[tokio::test]
+async fn pause_uses_stop_endpoint_on_newer_api() {
+    let server = MockServer::start().await.unwrap();
+    server.add_torrent(mock_torrent(HASH_A, "a.iso"));
+    let client = logged_in(&server).await;
+
+    client.torrent_pause(HASH_A).await.unwrap();
+    assert_eq!(server.torrent(HASH_A).unwrap()["state"], "pausedUP");
+    client.torrent_resume(HASH_A).await.unwrap();
+
+    server.set_versions("v5.0.2", "2.11.2");
+    let client = logged_in(&server).await;
+
+    client.torrent_pause(HASH_A).await.unwrap();
+    assert_eq!(server.torrent(HASH_A).unwrap()["state"], "stoppedUP");
+    assert_eq!(server.request_count("/api/v2/torrents/stop"), 1);
+    assert_eq!(server.request_count("/api/v2/torrents/pause"), 1);
+}
+
+#[tokio::test]
+async fn sync_main_data_deltas() {
+    let server = MockServer::start().await.unwrap();
+    server.add_torrent(mock_torrent(HASH_A, "a.iso"));
+    server.add_torrent(mock_torrent(HASH_B, "b.iso"));
+    server.add_category("linux", "/downloads/linux");
+    let client = logged_in(&server).await;
+
+    let full = client.sync_main_data(None).await.unwrap();
+    assert!(full.full_update);
+    assert_eq!(full.torrents.len(), 2);
+    assert_eq!(full.torrents[HASH_A].name.as_deref(), Some("a.iso"));
+    assert_eq!(full.categories["linux"].save_path.as_deref(), Some("/downloads/linux"));
+
+    server.update_torrent(HASH_A, json!({ "upspeed": 1024 }));
+    server.remove_torrent(HASH_B);
+
+    // only the changed fields are sent
+    let delta = client.sync_main_data(Some(full.rid)).await.unwrap();
+    assert!(!delta.full_update);
+    assert_eq!(delta.torrents[HASH_A].upspeed, Some(1024));
+    assert_eq!(delta.torrents[HASH_A].name, None);
+    assert_eq!(delta.torrents_removed, [HASH_B]);
+    assert!(delta.categories.is_empty());
+    assert_eq!(delta.server_state.up_info_speed, Some(1024));
+    assert_eq!(delta.server_state.connection_status, None);
+
+    server.add_tag("linux");
+    let delta = client.sync_main_data(Some(delta.rid)).await.unwrap();
+    assert_eq!(delta.tags, ["linux"]);
+    assert!(delta.torrents.is_empty());
+}
+
+#[tokio::test]
+async fn speed_limits() {
+    let server = MockServer::start().await.unwrap();
+    let client = logged_in(&server).await;
+
+    client.set_download_limit(1_000_000).await.unwrap();
+    assert_eq!(client.download_limit().await.unwrap(), 1_000_000);
+
+    client.set_alternative_speed_limits(true).await.unwrap();
+    client.set_alternative_speed_limits(true).await.unwrap();
+    assert!(client.alternative_speed_limits_enabled().await.unwrap());
+    assert_eq!(server.request_count("/api/v2/transfer/toggleSpeedLimitsMode"), 1);
+
+    // limits of the alternative mode
+    assert_eq!(client.download_limit().await.unwrap(), 10240);
+    client.set_upload_limit(2048).await.unwrap();
+    assert_eq!(client.global_transfer_info().await.unwrap().up_rate_limit, 2048);
+
+    client.ban_peers("10.0.0.1:6881|10.0.0.2:6881").await.unwrap();
+    assert_eq!(server.banned_peers(), ["10.0.0.1:6881", "10.0.0.2:6881"]);
+}
+
+#[tokio::test]
+async fn bandwidth_scheduler_reconciles_limits() {
+    let server = MockServer::start().await.unwrap();
+    let client = QBittorrentClient::new(server.url()).await.unwrap();
+    client.set_credentials(MOCK_USERNAME, MOCK_PASSWORD).await;
+    client.login(MOCK_USERNAME, MOCK_PASSWORD).await.unwrap();
+
+    let limits = SpeedLimits { download: 1000, upload: 2000, alternative: None };
+    let mut scheduler = BandwidthScheduler::new(limits);
+
+    assert_eq!(scheduler.tick(&client).await.unwrap(), limits);
+    assert_eq!(client.download_limit().await.unwrap(), 1000);
+
+    // nothing is read or changed while the limits stay the same
+    scheduler.tick(&client).await.unwrap();
+    assert_eq!(server.request_count("/api/v2/transfer/downloadLimit"), 2);
+    assert_eq!(server.request_count("/api/v2/transfer/setDownloadLimit"), 1);
+
+    // qBittorrent restarted with its own limits, the client logs in again on the next request
+    logged_in(&server).await.set_download_limit(5).await.unwrap();
+    server.expire_sessions();
+    client.application_version().await.unwrap();
+
+    scheduler.tick(&client).await.unwrap();
+    assert_eq!(server.request_count("/api/v2/auth/login"), 3);
+    assert_eq!(client.download_limit().await.unwrap(), 1000);
+    assert_eq!(client.upload_limit().await.unwrap(), 2000);
+
+    // switching to the alternative mode reads the limits of that mode again
+    let alternative = SpeedLimits { download: 300, upload: 400, alternative: Some(true) };
+    let mut scheduler = BandwidthScheduler::new(alternative);
+    let reads = server.request_count("/api/v2/transfer/downloadLimit");
+
+    scheduler.tick(&client).await.unwrap();
+    assert!(client.alternative_speed_limits_enabled().await.unwrap());
+    assert_eq!(server.request_count("/api/v2/transfer/downloadLimit"), reads + 1);
+    assert_eq!(client.download_limit().await.unwrap(), 300);
+
+    client.set_alternative_speed_limits(false).await.unwrap();
+    assert_eq!(client.download_limit().await.unwrap(), 1000);
+}
+
+#[tokio::test]
+async fn apply_preferences() {
+    let server = MockServer::start().await.unwrap();
+    let client = logged_in(&server).await;
+
+    let desired = AppPreferences {
+        listen_port: Some(51413),
+        dht: Some(true),
+        ..Default::default()
+    };
+
+    let changes = client.apply_preferences(&desired, true).await.unwrap();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].key, "listen_port");
+    assert_eq!(server.preferences()["listen_port"], 6881);
+
+    client.apply_preferences(&desired, false).await.unwrap();
+    assert_eq!(server.preferences()["listen_port"], 51413);
+    assert!(client.apply_preferences(&desired, true).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn logs_and_tail() {
+    let server = MockServer::start().await.unwrap();
+    server.push_log(LogLevel::Info, "qBittorrent v4.6.5 started");
+    server.push_log(LogLevel::Warning, "UPnP/NAT-PMP support: OFF");
+    server.push_peer_log("10.0.0.1", true, "IP filter");
+    let client = logged_in(&server).await;
+
+    let warnings = client.logs(LogParams {
+        normal: false,
+        info: false,
+        ..Default::default()
+    }).await.unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].log_type, LogLevel::Warning);
+
+    let peers = client.peer_logs(None).await.unwrap();
+    assert!(peers[0].blocked);
+
+    let mut logs = pin!(client.tail_logs(LogParams::default(), Duration::from_millis(10)));
+    assert_eq!(logs.next().await.unwrap().unwrap().id, 0);
+    assert_eq!(logs.next().await.unwrap().unwrap().id, 1);
+
+    server.push_log(LogLevel::Critical, "File error alert");
+    assert_eq!(logs.next().await.unwrap().unwrap().message, "File error alert");
+
+    // a log type added by a newer server doesn't break the tail
+    server.push_log(LogLevel::Other(16), "Unknown log type");
+    assert_eq!(logs.next().await.unwrap().unwrap().log_type, LogLevel::Other(16));
+    assert_eq!(client.logs(LogParams::default()).await.unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn retries_server_errors() {
+    let server = MockServer::start().await.unwrap();
+    let client = logged_in(&server).await;
+
+    server.fail_next("/api/v2/app/version", 503, 2);
+    assert_eq!(client.application_version().await.unwrap_err().status_code(), Some(503));
+
+    server.fail_next("/api/v2/app/version", 503, 2);
+    client.set_retry_policy(RetryPolicy {
+        initial_backoff: Duration::from_millis(1),
+        ..Default::default()
+    }).await;
+    assert!(client.application_version().await.is_ok());
+    assert_eq!(server.request_count("/api/v2/app/version"), 4);
+}
+
+#[tokio::test]
+async fn retries_dropped_connections() {
+    let server = MockServer::start().await.unwrap();
+    server.add_torrent(mock_torrent(HASH_A, "debian.iso"));
+    let client = logged_in(&server).await;
+
+    server.drop_next("/api/v2/torrents/info", 1);
+    assert!(matches!(client.torrent_list(TorrentListParams::default()).await, Err(QBittorrentError::Reqwest(_))));
+
+    client.set_retry_policy(RetryPolicy {
+        initial_backoff: Duration::from_millis(1),
+        ..Default::default()
+    }).await;
+
+    server.drop_next("/api/v2/torrents/info", 2);
+    assert_eq!(client.torrent_list(TorrentListParams::default()).await.unwrap().len(), 1);
+
+    // the body is read inside the retry loop, so a response cut short is retried too
+    server.truncate_next("/api/v2/torrents/info", 2);
+    assert_eq!(client.torrent_list(TorrentListParams::default()).await.unwrap().len(), 1);
+    assert_eq!(server.request_count("/api/v2/torrents/info"), 7);
+}
+
+#[tokio::test]
+async fn times_out_stalled_requests() {
+    let server = MockServer::start().await.unwrap();
+    let client = logged_in(&server).await;
+
+    client.set_retry_policy(RetryPolicy {
+        max_retries: 0,
+        timeout: Some(Duration::from_millis(50)),
+        ..Default::default()
+    }).await;
+
+    server.delay_next("/api/v2/app/version", Duration::from_secs(5), 1);
+    match client.application_version().await {
+        Err(QBittorrentError::Reqwest(err)) => assert!(err.is_timeout()),
+        res => panic!("expected a timeout, got {:?}", res)
+    }
+
+    client.set_retry_policy(RetryPolicy {
+        initial_backoff: Duration::from_millis(1),
+        timeout: Some(Duration::from_millis(50)),
+        ..Default::default()
+    }).await;
+
+    server.delay_next("/api/v2/app/version", Duration::from_secs(5), 1);
+    assert!(client.application_version().await.is_ok());
+}
@@ -0,0 +1,292 @@
+// Hand-written responses, not captured from qBittorrent. Each directory under tests/fixtures is one shape the models
+// have to accept:
+//   all_fields             every field the models know, string proxy_type, "private" torrent flag
+//   camel_case_is_private  "isPrivate" in torrent properties, no private flag in torrent lists
+//   int_proxy_type         integer proxy_type code, no seeding_time
+//   uint32_sentinels       4294967295 for unset timestamps, no availability, content_path or seeding_time
+//
+// Sanitized responses captured from real servers go to tests/fixtures/captured/<version>, see the README there.
+// None have been added yet, so the shapes above are not verified against any qBittorrent release.
+
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, UNIX_EPOCH}
+};
+
+use qbittorrent_client::models::{
+    AppPreferences, PartialTorrent, ProxyType, ShareLimit, SyncMainData, Torrent, TorrentGenericProperties
+};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+
+const SHAPES: [&str; 4] = ["all_fields", "camel_case_is_private", "int_proxy_type", "uint32_sentinels"];
+
+fn fixture<T: DeserializeOwned>(shape: &str, name: &str) -> T {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(shape).join(name);
+    let json = fs::read_to_string(&path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+
+    serde_json::from_str(&json).unwrap_or_else(|err| panic!("{}: {}", path.display(), err))
+}
+
+fn assert_round_trip<T: DeserializeOwned + Serialize + PartialEq + std::fmt::Debug>(shape: &str, name: &str) -> Value {
+    let parsed = fixture::<T>(shape, name);
+    let json = serde_json::to_value(&parsed).unwrap();
+
+    assert_eq!(serde_json::from_value::<T>(json.clone()).unwrap(), parsed, "{}/{}", shape, name);
+    json
+}
+
+#[test]
+fn torrent_list() {
+    for shape in SHAPES {
+        let torrents = fixture::<Vec<Torrent>>(shape, "torrents_info.json");
+        assert_eq!(torrents.len(), 3, "{}", shape);
+
+        let seeding = &torrents[0];
+        let completed = DateTime::<Utc>::from(seeding.completion_on_time().unwrap());
+        assert_eq!(completed.to_rfc3339(), "2024-06-04T12:01:11+00:00", "{}", shape);
+
+        let downloading = &torrents[1];
+        assert_eq!(downloading.state, "downloading", "{}", shape);
+        assert_eq!(downloading.completion_on, None, "{}", shape);
+        assert_eq!(downloading.completion_on_time(), None, "{}", shape);
+        assert_eq!(downloading.eta_duration(), Some(Duration::from_secs(318)), "{}", shape);
+
+        assert_eq!((downloading.max_ratio, downloading.max_seeding_time), (ShareLimit::Limit(2.0), ShareLimit::Limit(1440)), "{}", shape);
+
+        let magnet = &torrents[2];
+        assert_eq!((magnet.max_ratio, magnet.max_seeding_time), (ShareLimit::Unlimited, ShareLimit::Unlimited), "{}", shape);
+        assert_eq!(magnet.eta, None, "{}", shape);
+        assert_eq!(magnet.seen_complete, None, "{}", shape);
+    }
+}
+
+#[test]
+fn torrent_generic_properties() {
+    for shape in SHAPES {
+        let props = fixture::<TorrentGenericProperties>(shape, "torrent_properties.json");
+        assert_eq!(props.pieces_num, Some(15875), "{}", shape);
+        assert_eq!(props.completion_date, None, "{}", shape);
+        assert_eq!(props.addition_date_time(), UNIX_EPOCH + Duration::from_secs(1718109123), "{}", shape);
+
+        let props = fixture::<TorrentGenericProperties>(shape, "torrent_properties_metadata_missing.json");
+        assert_eq!(props.total_size, None, "{}", shape);
+        assert_eq!(props.piece_size, None, "{}", shape);
+        assert_eq!(props.creation_date_time(), None, "{}", shape);
+        assert_eq!(props.last_seen_time(), None, "{}", shape);
+    }
+}
+
+#[test]
+fn preferences_keep_known_fields_typed() {
+    // null is accepted by every known field, unknown keys end up in extra
+    let known = |key: &str| serde_json::from_value::<AppPreferences>(json!({ key: null })).unwrap().extra.is_empty();
+
+    for shape in SHAPES {
+        let prefs = fixture::<AppPreferences>(shape, "preferences.json");
+
+        // the tolerant deserializer moves keys it can't parse into extra, a known key there means a wrong field type
+        let mismatched = prefs.extra.keys()
+            .filter(|key| known(key))
+            .collect::<Vec<_>>();
+
+        assert!(mismatched.is_empty(), "{}: {:?}", shape, mismatched);
+        assert_eq!(prefs.listen_port, Some(6881), "{}", shape);
+        assert!(prefs.proxy_type.is_some(), "{}", shape);
+    }
+
+    let prefs = fixture::<AppPreferences>("int_proxy_type", "preferences.json");
+    assert_eq!(prefs.proxy_type, Some(ProxyType::Legacy(4)));
+    assert_eq!(serde_json::to_value(&prefs).unwrap()["proxy_type"], json!(4));
+}
+
+#[test]
+fn preferences_patch_serializes_only_set_fields() {
+    assert_eq!(serde_json::to_value(AppPreferences::default()).unwrap(), json!({}));
+
+    let patch = AppPreferences { max_active_downloads: Some(5), dht: Some(false), ..Default::default() };
+    assert_eq!(serde_json::to_value(patch).unwrap(), json!({ "max_active_downloads": 5, "dht": false }));
+}
+
+#[test]
+fn proxy_type_keeps_its_wire_form() {
+    let prefs = serde_json::from_value::<AppPreferences>(json!({ "proxy_type": 3 })).unwrap();
+    let proxy_type = prefs.proxy_type.clone().unwrap();
+
+    assert_eq!(proxy_type, ProxyType::Legacy(3));
+    assert_eq!(proxy_type.kind(), ProxyType::Http);
+    assert_eq!(proxy_type.legacy_auth(), Some(true));
+    assert_eq!(serde_json::to_value(&prefs).unwrap(), json!({ "proxy_type": 3 }));
+
+    let prefs = serde_json::from_value::<AppPreferences>(json!({ "proxy_type": "SOCKS5" })).unwrap();
+
+    assert_eq!(prefs.proxy_type, Some(ProxyType::Socks5));
+    assert_eq!(prefs.proxy_type.as_ref().unwrap().legacy_auth(), None);
+    assert_eq!(serde_json::to_value(&prefs).unwrap(), json!({ "proxy_type": "SOCKS5" }));
+}
+
+#[test]
+fn share_limits() {
+    let limits = serde_json::from_value::<Vec<ShareLimit<f64>>>(json!([-2, -1, 0, 1.5])).unwrap();
+    assert_eq!(limits, [ShareLimit::Global, ShareLimit::Unlimited, ShareLimit::Limit(0.0), ShareLimit::Limit(1.5)]);
+    assert_eq!(serde_json::to_value(&limits).unwrap(), json!([-2, -1, 0.0, 1.5]));
+
+    let changes = serde_json::from_value::<PartialTorrent>(json!({ "max_ratio": -2, "max_seeding_time": 1440 })).unwrap();
+    assert_eq!((changes.max_ratio, changes.max_seeding_time), (Some(ShareLimit::Global), Some(ShareLimit::Limit(1440))));
+    assert_eq!(serde_json::to_value(&changes).unwrap(), json!({ "max_ratio": -2, "max_seeding_time": 1440 }));
+}
+
+#[test]
+fn main_data_deltas() {
+    for shape in SHAPES {
+        let full = fixture::<SyncMainData>(shape, "maindata_full.json");
+        let delta = fixture::<SyncMainData>(shape, "maindata_delta.json");
+        assert!(full.full_update && !delta.full_update, "{}", shape);
+
+        assert_eq!(full.torrents.len(), 3, "{}", shape);
+        assert_eq!(delta.rid, 2, "{}", shape);
+        assert_eq!(delta.torrents_removed.len(), 1, "{}", shape);
+
+        // deltas only carry the changed fields
+        let (hash, changes) = delta.torrents.iter().next().unwrap();
+        assert_eq!(changes.eta, Some(Some(309)), "{}", shape);
+        assert_eq!(changes.name, None, "{}", shape);
+        assert_eq!(full.torrents[hash].name.as_deref(), Some("ubuntu-24.04-desktop-amd64.iso"), "{}", shape);
+
+        assert!(!full.categories.is_empty(), "{}", shape);
+        assert_eq!(delta.server_state.dl_info_speed, Some(8319221), "{}", shape);
+    }
+}
+
+#[test]
+fn models_round_trip() {
+    for shape in SHAPES {
+        assert_round_trip::<Vec<Torrent>>(shape, "torrents_info.json");
+        assert_round_trip::<TorrentGenericProperties>(shape, "torrent_properties.json");
+        assert_round_trip::<TorrentGenericProperties>(shape, "torrent_properties_metadata_missing.json");
+        assert_round_trip::<AppPreferences>(shape, "preferences.json");
+        assert_round_trip::<SyncMainData>(shape, "maindata_full.json");
+        assert_round_trip::<SyncMainData>(shape, "maindata_delta.json");
+    }
+}
+
+// Every file present in a captured/<version> directory has to parse and round trip like the hand-written shapes
+#[test]
+fn captured_responses() {
+    let captured = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/captured");
+
+    for entry in fs::read_dir(captured).unwrap() {
+        let path = entry.unwrap().path();
+
+        if !path.is_dir() {
+            continue;
+        }
+
+        let shape = format!("captured/{}", path.file_name().unwrap().to_string_lossy());
+        let exists = |name: &str| path.join(name).exists();
+
+        if exists("torrents_info.json") {
+            assert_round_trip::<Vec<Torrent>>(&shape, "torrents_info.json");
+        }
+
+        for name in ["torrent_properties.json", "torrent_properties_metadata_missing.json"] {
+            if exists(name) {
+                assert_round_trip::<TorrentGenericProperties>(&shape, name);
+            }
+        }
+
+        if exists("preferences.json") {
+            assert_round_trip::<AppPreferences>(&shape, "preferences.json");
+        }
+
+        for name in ["maindata_full.json", "maindata_delta.json"] {
+            if exists(name) {
+                assert_round_trip::<SyncMainData>(&shape, name);
+            }
+        }
+    }
+}
+
+// Sentinels are written back in the form current qBittorrent versions use (see src/sentinel.rs),
+// so e.g. a 4294967295 timestamp is serialized as -1 and a -1 limit as 0
+fn normalized(key: &str, value: &Value) -> Value {
+    let Some(number) = value.as_f64() else {
+        return value.clone();
+    };
+
+    let unset_timestamp = number <= 0.0 || number == u32::MAX as f64;
+
+    match key {
+        "completion_on" | "seen_complete" | "creation_date" | "completion_date" | "last_seen" if unset_timestamp => json!(-1),
+        "last_activity" if unset_timestamp => json!(0),
+        "dl_limit" | "up_limit" | "priority" | "nb_connections_limit" if number <= 0.0 => json!(0),
+        "eta" if number >= 8640000.0 => json!(8640000),
+        "availability" | "piece_size" | "pieces_have" | "pieces_num" | "reannounce" | "total_size" if number < 0.0 => json!(-1),
+        _ => value.clone()
+    }
+}
+
+// Every recorded field the model knows is written back under the same name, with sentinels normalized.
+// Fields missing from a response are written as None (or its sentinel), and must be one of `missing`
+fn assert_serialized_normalized(shape: &str, recorded: &Value, serialized: &Value, renamed: (&str, &str), missing: &[&str]) {
+    let serialized = serialized.as_object().unwrap();
+
+    for (key, value) in serialized {
+        let recorded_key = if key == renamed.1 && recorded.get(key).is_none() { renamed.0 } else { key.as_str() };
+
+        let Some(recorded) = recorded.get(recorded_key) else {
+            assert!(missing.contains(&key.as_str()), "{}: {} is not in the response", shape, key);
+            continue;
+        };
+
+        let expected = normalized(key, recorded);
+        assert!(same(&expected, value), "{}: {}: {} was written as {}, expected {}", shape, key, recorded, value, expected);
+    }
+}
+
+// f64 fields are written as floats, e.g. -1.0 for a recorded -1
+fn same(a: &Value, b: &Value) -> bool {
+    a == b || (a.is_number() && a.as_f64() == b.as_f64())
+}
+
+#[test]
+fn serialization_normalizes_sentinels() {
+    for shape in SHAPES {
+        let recorded = fixture::<Vec<Value>>(shape, "torrents_info.json");
+        let serialized = assert_round_trip::<Vec<Torrent>>(shape, "torrents_info.json");
+
+        for (recorded, serialized) in recorded.iter().zip(serialized.as_array().unwrap()) {
+            assert_serialized_normalized(shape, recorded, serialized, ("isPrivate", "private"), &["availability", "content_path", "seeding_time", "private"]);
+        }
+
+        for name in ["torrent_properties.json", "torrent_properties_metadata_missing.json"] {
+            let recorded = fixture::<Value>(shape, name);
+            let serialized = assert_round_trip::<TorrentGenericProperties>(shape, name);
+
+            assert_serialized_normalized(shape, &recorded, &serialized, ("isPrivate", "is_private"), &["is_private"]);
+        }
+
+        // preferences have no sentinels and are written back unchanged, including keys unknown to AppPreferences
+        let recorded = fixture::<Value>(shape, "preferences.json");
+        let serialized = fixture::<AppPreferences>(shape, "preferences.json").to_json_map();
+        let recorded = recorded.as_object().unwrap();
+
+        assert_eq!(serialized.keys().collect::<Vec<_>>(), recorded.keys().collect::<Vec<_>>(), "{}", shape);
+        for (key, value) in &serialized {
+            assert!(same(&recorded[key], value), "{}: {}: {} was written as {}", shape, key, recorded[key], value);
+        }
+    }
+
+    // e.g. an unset uint32 timestamp, and a -1 limit and queue position
+    let recorded = fixture::<Vec<Value>>("uint32_sentinels", "torrents_info.json");
+    let serialized = serde_json::to_value(fixture::<Vec<Torrent>>("uint32_sentinels", "torrents_info.json")).unwrap();
+    let unset = recorded.iter().position(|torrent| torrent["completion_on"] == json!(u32::MAX)).unwrap();
+    assert_eq!(serialized[unset]["completion_on"], json!(-1));
+
+    let recorded = fixture::<Vec<Value>>("all_fields", "torrents_info.json");
+    let serialized = serde_json::to_value(fixture::<Vec<Torrent>>("all_fields", "torrents_info.json")).unwrap();
+    assert_eq!((&recorded[0]["dl_limit"], &recorded[0]["priority"]), (&json!(-1), &json!(-1)));
+    assert_eq!((&serialized[0]["dl_limit"], &serialized[0]["priority"]), (&json!(0), &json!(0)));
+}